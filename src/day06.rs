@@ -1,17 +1,50 @@
 use rayon::prelude::*;
-use std::{collections::HashSet, fmt::Display, time::Instant};
+use std::{
+    collections::HashSet,
+    fmt::{Display, Write},
+    time::Instant,
+};
 
 pub fn run_outer() -> String {
+    run_outer_with(TurnPolicy::Right, false, None)
+}
+
+/// Run with the guard turning a different way at obstacles to the puzzle, and optionally list
+/// every position where a new obstacle makes the guard loop, or draw the loop caused by an
+/// obstacle at `loop_at`.
+pub fn run_outer_with(
+    policy: TurnPolicy,
+    list_obstacles: bool,
+    loop_at: Option<(usize, usize)>,
+) -> String {
     let input = include_str!("../inputs/06.in");
+    let grid_state = GridState::parse(input).with_policy(policy);
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let Some((pt1, obstacles)) = run_inner(&grid_state) else {
+        return "the guard never leaves the area".to_string();
+    };
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
         pt1,
-        pt2,
+        obstacles.len(),
         elapsed.as_micros()
-    )
+    );
+
+    if list_obstacles {
+        for (x, y) in obstacles {
+            write!(out, "\n{x},{y}").unwrap();
+        }
+    }
+
+    if let Some((x, y)) = loop_at {
+        match grid_state.loop_path((x, y)) {
+            Some(loop_state) => write!(out, "\n{}", loop_state.to_string().trim_end()).unwrap(),
+            None => write!(out, "\nan obstacle at {x},{y} doesn't make the guard loop").unwrap(),
+        }
+    }
+
+    out
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -43,6 +76,24 @@ impl Direction {
         }
     }
 
+    fn prev(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+
     fn to_numerical(&self) -> (isize, isize) {
         match self {
             Direction::Up => (0, -1),
@@ -60,41 +111,178 @@ enum GuardState {
     InfiniteLoop,
 }
 
+/// Which way a guard turns when it hits an obstacle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum TurnPolicy {
+    /// Always turn right
+    Right,
+    /// Always turn left
+    Left,
+    /// Turn right, then left, then right, and so on
+    Alternate,
+}
+
+impl TurnPolicy {
+    /// Direction to face after hitting an obstacle, given the number of turns made so far.
+    fn turn(&self, direction: Direction, turns: usize) -> Direction {
+        match (self, turns % 2) {
+            (TurnPolicy::Right, _) | (TurnPolicy::Alternate, 0) => direction.next(),
+            _ => direction.prev(),
+        }
+    }
+
+    /// The part of the number of turns made so far which affects future turns. Two guards in the
+    /// same place facing the same way only behave the same if this matches too.
+    fn phase(&self, turns: usize) -> usize {
+        match self {
+            TurnPolicy::Alternate => turns % 2,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Guard {
+    /// Current position of the guard in the grid: (x, y)
+    position: (usize, usize),
+
+    /// Current direction the guard is facing
+    direction: Direction,
+
+    /// How the guard turns at obstacles
+    policy: TurnPolicy,
+
+    /// Number of times the guard has turned
+    turns: usize,
+
+    state: GuardState,
+
+    /// Locations visited, along with the turn policy phase at the time
+    visited: HashSet<(usize, usize, Direction, usize)>,
+}
+
+impl Guard {
+    fn new(position: (usize, usize), direction: Direction) -> Self {
+        let mut guard = Guard {
+            position,
+            direction,
+            policy: TurnPolicy::Right,
+            turns: 0,
+            state: GuardState::Patrolling,
+            visited: HashSet::new(),
+        };
+        guard.visited.insert(guard.key());
+        guard
+    }
+
+    fn key(&self) -> (usize, usize, Direction, usize) {
+        (
+            self.position.0,
+            self.position.1,
+            self.direction,
+            self.policy.phase(self.turns),
+        )
+    }
+
+    fn would_exceed_bounds(&self, movement: (isize, isize), dimensions: (usize, usize)) -> bool {
+        let maxx = dimensions.0 - 1;
+        let maxy = dimensions.1 - 1;
+
+        match (self.position, movement) {
+            ((0, _), (-1, _)) => true,
+            ((_, 0), (_, -1)) => true,
+            ((x, _), (1, _)) if x == maxx => true,
+            ((_, y), (_, 1)) if y == maxy => true,
+            _ => false,
+        }
+    }
+
+    /// Move or turn the guard once. A guard which is stuck in a loop keeps going round it, but
+    /// once a guard has left the area it stays gone.
+    fn step(&mut self, obstacles: &HashSet<(usize, usize)>, dimensions: (usize, usize)) {
+        if self.state == GuardState::LeftArea {
+            return;
+        }
+
+        let (x, y) = self.position;
+        let (dx, dy) = self.direction.to_numerical();
+
+        if self.would_exceed_bounds((dx, dy), dimensions) {
+            // We're done, guard goes out of bounds
+            self.state = GuardState::LeftArea;
+            return;
+        }
+
+        // We have already bounds checked this
+        let newx = (x as isize + dx) as usize;
+        let newy = (y as isize + dy) as usize;
+
+        if obstacles.contains(&(newx, newy)) {
+            self.direction = self.policy.turn(self.direction, self.turns);
+            self.turns += 1;
+        } else {
+            self.position = (newx, newy);
+        }
+
+        if !self.visited.insert(self.key()) && self.state == GuardState::Patrolling {
+            // Position and direction was already visited, so we have an infinite loop
+            self.state = GuardState::InfiniteLoop;
+        }
+    }
+
+    fn unique_positions(&self) -> HashSet<(usize, usize)> {
+        self.visited.iter().map(|(x, y, _, _)| (*x, *y)).collect()
+    }
+}
+
+/// Two guards which ended up on the same cell, or swapped cells, during a single step.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Collision {
+    /// Number of steps taken when the collision happened
+    step: usize,
+
+    /// Indices of the two guards which collided
+    guards: (usize, usize),
+
+    /// Where the first guard was after the step
+    position: (usize, usize),
+}
+
 #[derive(Debug, Clone)]
 struct GridState {
     /// Dimensions of the whole grid: (x, y)
     dimensions: (usize, usize),
 
-    /// Current position of the guard in the grid: (x, y)
-    guard_position: (usize, usize),
-
-    /// Current direction the guard is facing
-    guard_direction: Direction,
+    /// All of the guards, in the order they appear in the grid
+    guards: Vec<Guard>,
 
     /// Obstacles in the grid
     obstacles: HashSet<(usize, usize)>,
 
-    /// Locations visited
-    visited: HashSet<(usize, usize, Direction)>,
+    /// Obstacle added on top of the original grid, if any
+    added_obstacle: Option<(usize, usize)>,
+
+    /// Number of steps taken so far
+    steps: usize,
+
+    /// Every collision between guards so far
+    collisions: Vec<Collision>,
 }
 
 impl GridState {
     fn parse(input: &str) -> GridState {
         let mut dimensions = (0, 0);
-        let mut guard_position: Option<(usize, usize)> = None;
-        let mut guard_direction: Option<Direction> = None;
+        let mut guards: Vec<Guard> = Vec::new();
         let mut obstacles: HashSet<(usize, usize)> = HashSet::new();
 
         for (y, line) in input.trim().lines().enumerate() {
-            // Check if the guard is in this line
-            if guard_position.is_none() {
+            for (x, c) in line.chars().enumerate() {
                 use Direction::*;
 
+                // Check if there is a guard here
                 for dir in [Up, Down, Left, Right] {
-                    let c: char = dir.into();
-                    if let Some(x) = line.find(c) {
-                        guard_position = Some((x, y));
-                        guard_direction = Some(dir);
+                    if c == dir.into() {
+                        guards.push(Guard::new((x, y), dir));
                     }
                 }
             }
@@ -109,63 +297,84 @@ impl GridState {
             dimensions.1 = y + 1;
         }
 
-        let guard_position = guard_position.unwrap();
-        let guard_direction = guard_direction.unwrap();
-
-        let mut visited = HashSet::new();
-        visited.insert((guard_position.0, guard_position.1, guard_direction));
+        assert!(!guards.is_empty(), "no guards in grid");
 
         GridState {
             dimensions,
-            guard_position,
-            guard_direction,
+            guards,
             obstacles,
-            visited,
+            added_obstacle: None,
+            steps: 0,
+            collisions: Vec::new(),
         }
     }
 
-    fn would_exceed_bounds(&self, movement: (isize, isize)) -> bool {
-        let maxx = self.dimensions.0 - 1;
-        let maxy = self.dimensions.1 - 1;
+    fn with_obstacle(&self, pos: (usize, usize)) -> GridState {
+        let mut gs = self.clone();
+        gs.obstacles.insert(pos);
+        gs.added_obstacle = Some(pos);
+        gs
+    }
 
-        match (self.guard_position, movement) {
-            ((0, _), (-1, _)) => true,
-            ((_, 0), (_, -1)) => true,
-            ((x, _), (1, _)) if x == maxx => true,
-            ((_, y), (_, 1)) if y == maxy => true,
-            _ => false,
+    /// Set the turn policy of every guard. Should be used before any steps are taken.
+    fn with_policy(&self, policy: TurnPolicy) -> GridState {
+        let mut gs = self.clone();
+        for guard in gs.guards.iter_mut() {
+            guard.policy = policy;
+            guard.visited = HashSet::from([guard.key()]);
         }
+        gs
     }
 
-    fn step(&mut self) -> GuardState {
-        let (x, y) = self.guard_position;
-        let (dx, dy) = self.guard_direction.to_numerical();
+    /// The only guard in the grid, for the puzzle itself which only ever has one.
+    fn guard(&self) -> &Guard {
+        assert_eq!(self.guards.len(), 1, "expected a single guard");
+        &self.guards[0]
+    }
 
-        if self.would_exceed_bounds((dx, dy)) {
-            // We're done, guard goes out of bounds
-            return GuardState::LeftArea;
+    /// Overall state of the guards: patrolling if any guard still is, otherwise looping if any
+    /// guard got stuck in a loop.
+    fn state(&self) -> GuardState {
+        let states: Vec<GuardState> = self.guards.iter().map(|guard| guard.state).collect();
+
+        if states.contains(&GuardState::Patrolling) {
+            GuardState::Patrolling
+        } else if states.contains(&GuardState::InfiniteLoop) {
+            GuardState::InfiniteLoop
+        } else {
+            GuardState::LeftArea
         }
+    }
 
-        // We have already bounds checked this
-        let newx = (x as isize + dx) as usize;
-        let newy = (y as isize + dy) as usize;
+    fn step(&mut self) -> GuardState {
+        let before: Vec<(usize, usize)> = self.guards.iter().map(|guard| guard.position).collect();
 
-        if self.obstacles.contains(&(newx, newy)) {
-            self.guard_direction = self.guard_direction.next();
-        } else {
-            self.guard_position = (newx, newy);
+        for guard in self.guards.iter_mut() {
+            guard.step(&self.obstacles, self.dimensions);
         }
 
-        if !self.visited.insert((
-            self.guard_position.0,
-            self.guard_position.1,
-            self.guard_direction,
-        )) {
-            // Position and direction was already visited, so we have an infinite loop
-            return GuardState::InfiniteLoop;
+        self.steps += 1;
+
+        for i in 0..self.guards.len() {
+            for j in (i + 1)..self.guards.len() {
+                let (a, b) = (&self.guards[i], &self.guards[j]);
+                if a.state == GuardState::LeftArea || b.state == GuardState::LeftArea {
+                    continue;
+                }
+
+                let same_cell = a.position == b.position;
+                let swapped = a.position == before[j] && b.position == before[i];
+                if same_cell || swapped {
+                    self.collisions.push(Collision {
+                        step: self.steps,
+                        guards: (i, j),
+                        position: a.position,
+                    });
+                }
+            }
         }
 
-        GuardState::Patrolling
+        self.state()
     }
 
     fn step_until_end(&mut self) -> GuardState {
@@ -178,12 +387,181 @@ impl GridState {
     }
 
     fn unique_positions(&self) -> HashSet<(usize, usize)> {
-        self.visited.iter().map(|(x, y, _)| (*x, *y)).collect()
+        self.guards
+            .iter()
+            .flat_map(|guard| guard.unique_positions())
+            .collect()
     }
 
     fn count_visited(&self) -> usize {
         self.unique_positions().len()
     }
+
+    /// If adding an obstacle at `pos` makes the guard loop, return the state with only the loop
+    /// itself marked as visited, not the path the guard took to get there.
+    fn loop_path(&self, pos: (usize, usize)) -> Option<GridState> {
+        self.guard();
+
+        let mut gs = self.with_obstacle(pos);
+        if gs.step_until_end() != GuardState::InfiniteLoop {
+            return None;
+        }
+
+        // The guard is now on a position and direction it has already visited, which must be part
+        // of the loop. Go round once more from here to find the rest of it.
+        let guard = &mut gs.guards[0];
+        guard.visited = HashSet::from([guard.key()]);
+        guard.state = GuardState::Patrolling;
+        gs.step_until_end();
+
+        Some(gs)
+    }
+}
+
+/// For every cell and direction, where the guard stops if it walks in that direction until it
+/// hits an obstacle, or `None` if it walks out of the area first. This lets the guard jump
+/// straight from one turning point to the next.
+#[derive(Debug, Clone)]
+struct JumpTable {
+    dimensions: (usize, usize),
+    stops: Vec<[Option<(usize, usize)>; 4]>,
+}
+
+impl JumpTable {
+    fn new(grid_state: &GridState) -> Self {
+        let (width, height) = grid_state.dimensions;
+        let mut stops = vec![[None; 4]; width * height];
+        let is_obstacle = |x: usize, y: usize| grid_state.obstacles.contains(&(x, y));
+
+        for x in 0..width {
+            let mut stop = None;
+            for y in 0..height {
+                if is_obstacle(x, y) {
+                    stop = Some((x, y + 1));
+                } else {
+                    stops[y * width + x][Direction::Up.index()] = stop;
+                }
+            }
+
+            let mut stop = None;
+            for y in (0..height).rev() {
+                if is_obstacle(x, y) {
+                    stop = y.checked_sub(1).map(|y| (x, y));
+                } else {
+                    stops[y * width + x][Direction::Down.index()] = stop;
+                }
+            }
+        }
+
+        for y in 0..height {
+            let mut stop = None;
+            for x in 0..width {
+                if is_obstacle(x, y) {
+                    stop = Some((x + 1, y));
+                } else {
+                    stops[y * width + x][Direction::Left.index()] = stop;
+                }
+            }
+
+            let mut stop = None;
+            for x in (0..width).rev() {
+                if is_obstacle(x, y) {
+                    stop = x.checked_sub(1).map(|x| (x, y));
+                } else {
+                    stops[y * width + x][Direction::Right.index()] = stop;
+                }
+            }
+        }
+
+        Self {
+            dimensions: grid_state.dimensions,
+            stops,
+        }
+    }
+
+    /// Where the guard stops when walking from `pos` in `direction`, taking into account an extra
+    /// obstacle which isn't in the table.
+    fn jump(
+        &self,
+        pos: (usize, usize),
+        direction: Direction,
+        extra_obstacle: Option<(usize, usize)>,
+    ) -> Option<(usize, usize)> {
+        let stop = self.stops[pos.1 * self.dimensions.0 + pos.0][direction.index()];
+
+        let Some(obstacle) = extra_obstacle else {
+            return stop;
+        };
+
+        // Distance to the extra obstacle, if it's directly ahead of us
+        let obstacle_distance = match direction {
+            Direction::Up if obstacle.0 == pos.0 && obstacle.1 < pos.1 => pos.1 - obstacle.1,
+            Direction::Down if obstacle.0 == pos.0 && obstacle.1 > pos.1 => obstacle.1 - pos.1,
+            Direction::Left if obstacle.1 == pos.1 && obstacle.0 < pos.0 => pos.0 - obstacle.0,
+            Direction::Right if obstacle.1 == pos.1 && obstacle.0 > pos.0 => obstacle.0 - pos.0,
+            _ => return stop,
+        };
+
+        let stop_distance = stop.map(|stop| pos.0.abs_diff(stop.0) + pos.1.abs_diff(stop.1));
+        if stop_distance.is_some_and(|d| d < obstacle_distance) {
+            return stop;
+        }
+
+        // The extra obstacle is closer, so stop just before it
+        let (dx, dy) = direction.to_numerical();
+        Some((
+            (obstacle.0 as isize - dx) as usize,
+            (obstacle.1 as isize - dy) as usize,
+        ))
+    }
+
+    /// Whether a guard loops forever, starting from where it is now. Only turning points are
+    /// recorded, since if the guard loops it must repeat a turn.
+    fn guard_loops(&self, guard: &Guard, extra_obstacle: Option<(usize, usize)>) -> bool {
+        let mut pos = guard.position;
+        let mut direction = guard.direction;
+        let mut turns = guard.turns;
+        let mut turning_points: HashSet<((usize, usize), Direction, usize)> = HashSet::new();
+
+        while let Some(stop) = self.jump(pos, direction, extra_obstacle) {
+            pos = stop;
+            if !turning_points.insert((pos, direction, guard.policy.phase(turns))) {
+                return true;
+            }
+            direction = guard.policy.turn(direction, turns);
+            turns += 1;
+        }
+
+        false
+    }
+}
+
+/// Find every position where adding a single obstacle makes the guard loop, sorted by (x, y).
+fn loop_obstacles(initial_grid_state: &GridState) -> Vec<(usize, usize)> {
+    let mut grid_state = initial_grid_state.clone();
+    let end_state = grid_state.step_until_end();
+    assert!(end_state == GuardState::LeftArea);
+
+    // We only need to try adding obstacles at positions that were visited without one. Otherwise
+    // the guard will never hit the obstacle. We also need to remove the guard's initial position
+    // from the list of places to try putting obstacles.
+    let mut possible_obstacle_locations = grid_state.unique_positions();
+    let guard = initial_grid_state.guard();
+    possible_obstacle_locations.remove(&guard.position);
+
+    // To parallelise calculations, we need a vec rather than a hashset
+    let possible_locations_vec: Vec<(usize, usize)> =
+        possible_obstacle_locations.into_iter().collect();
+
+    let table = JumpTable::new(initial_grid_state);
+
+    let mut locations: Vec<(usize, usize)> = possible_locations_vec
+        .into_par_iter()
+        .filter(|pos| table.guard_loops(guard, Some(*pos)))
+        .collect();
+
+    locations.sort();
+    locations
 }
 
 impl Display for GridState {
@@ -192,10 +570,12 @@ impl Display for GridState {
 
         for y in 0..self.dimensions.1 {
             for x in 0..self.dimensions.0 {
-                if self.obstacles.contains(&(x, y)) {
+                if self.added_obstacle == Some((x, y)) {
+                    write!(f, "O")?;
+                } else if self.obstacles.contains(&(x, y)) {
                     write!(f, "#")?;
-                } else if (x, y) == self.guard_position {
-                    match self.guard_direction {
+                } else if let Some(guard) = self.guards.iter().find(|g| g.position == (x, y)) {
+                    match guard.direction {
                         Direction::Up => write!(f, "^")?,
                         Direction::Right => write!(f, ">")?,
                         Direction::Down => write!(f, "v")?,
@@ -214,53 +594,200 @@ impl Display for GridState {
     }
 }
 
-fn run(input: &str) -> (u64, u64) {
-    let initial_grid_state = GridState::parse(input);
-
+/// The number of positions visited, and the positions where an obstacle makes the guard loop, or
+/// `None` if the guard loops without any new obstacle.
+fn run_inner(initial_grid_state: &GridState) -> Option<(u64, Vec<(usize, usize)>)> {
     let mut grid_state = initial_grid_state.clone();
-    let end_state = grid_state.step_until_end();
-    assert!(end_state == GuardState::LeftArea);
+    if grid_state.step_until_end() != GuardState::LeftArea {
+        return None;
+    }
 
     let pt1 = grid_state.count_visited() as u64;
 
-    // For part 2, we only need to try adding obstacles at positions that were visited in part 1.
-    // Otherwise the guard will never hit the obstacle. We also need to remove the guard's initial
-    // position from the list of places to try putting obstacles.
-    let mut possible_obstacle_locations = grid_state.unique_positions();
-    possible_obstacle_locations.remove(&initial_grid_state.guard_position);
-    let possible_obstacle_locations = possible_obstacle_locations; // no longer needs to be mutable
-
-    // To parallelise calculations, we need a vec rather than a hashset
-    let possible_locations_vec: Vec<(usize, usize)> = possible_obstacle_locations
-        .iter()
-        .map(|item| *item)
-        .collect();
-
-    let pt2 = possible_locations_vec
-        .into_par_iter()
-        .filter(|(x, y)| {
-            // Create a new grid and insert the obstacle
-            let mut gs = initial_grid_state.clone();
-            gs.obstacles.insert((*x, *y));
-
-            let end_state = gs.step_until_end();
+    let pt2 = loop_obstacles(initial_grid_state);
 
-            end_state == GuardState::InfiniteLoop
-        })
-        .count() as u64;
-
-    (pt1, pt2)
+    Some((pt1, pt2))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use itertools::Itertools;
 
     #[test]
     fn test_example() {
         let input = include_str!("../inputs/06.ex");
-        let (pt1, pt2) = run(&input);
+        let (pt1, pt2) = run_inner(&GridState::parse(input)).unwrap();
         assert_eq!(pt1, 41);
-        assert_eq!(pt2, 6);
+        assert_eq!(pt2.len(), 6);
+    }
+
+    #[test]
+    fn test_loop_obstacles() {
+        let input = include_str!("../inputs/06.ex");
+        let grid_state = GridState::parse(input);
+        assert_eq!(
+            loop_obstacles(&grid_state),
+            vec![(1, 8), (3, 6), (3, 8), (6, 7), (7, 7), (7, 9)]
+        );
+    }
+
+    #[test]
+    fn test_loop_path() {
+        let input = include_str!("../inputs/06.ex");
+        let grid_state = GridState::parse(input);
+
+        let loop_state = grid_state.loop_path((3, 6)).unwrap();
+        assert_eq!(
+            loop_state.to_string(),
+            "....#.....\n\
+             ....XXXXX#\n\
+             ....X...X.\n\
+             ..#.X...X.\n\
+             ....X..#X.\n\
+             ....X...X.\n\
+             .#.O^XXXX.\n\
+             ........#.\n\
+             #.........\n\
+             ......#...\n"
+        );
+
+        assert!(grid_state.loop_path((0, 0)).is_none());
+    }
+
+    #[test]
+    fn test_jump_table() {
+        let input = include_str!("../inputs/06.ex");
+        let grid_state = GridState::parse(input);
+        let table = JumpTable::new(&grid_state);
+
+        assert_eq!(table.jump((4, 6), Direction::Up, None), Some((4, 1)));
+        assert_eq!(table.jump((4, 1), Direction::Right, None), Some((8, 1)));
+        assert_eq!(table.jump((8, 1), Direction::Down, None), Some((8, 6)));
+        assert_eq!(table.jump((8, 6), Direction::Left, None), Some((2, 6)));
+        assert_eq!(table.jump((7, 7), Direction::Down, None), None);
+        assert_eq!(table.jump((0, 0), Direction::Up, None), None);
+
+        // Extra obstacles only matter if they're ahead of the guard and before the next obstacle
+        assert_eq!(
+            table.jump((8, 6), Direction::Left, Some((3, 6))),
+            Some((4, 6))
+        );
+        assert_eq!(
+            table.jump((8, 6), Direction::Left, Some((7, 6))),
+            Some((8, 6))
+        );
+        assert_eq!(
+            table.jump((8, 6), Direction::Left, Some((0, 6))),
+            Some((2, 6))
+        );
+        assert_eq!(
+            table.jump((8, 6), Direction::Left, Some((9, 6))),
+            Some((2, 6))
+        );
+        assert_eq!(
+            table.jump((8, 6), Direction::Left, Some((3, 5))),
+            Some((2, 6))
+        );
+        assert_eq!(
+            table.jump((7, 7), Direction::Down, Some((7, 9))),
+            Some((7, 8))
+        );
+    }
+
+    #[test]
+    fn test_jump_table_matches_step() {
+        let input = include_str!("../inputs/06.ex");
+        let grid_state = GridState::parse(input);
+        let table = JumpTable::new(&grid_state);
+
+        for y in 0..grid_state.dimensions.1 {
+            for x in 0..grid_state.dimensions.0 {
+                if grid_state.obstacles.contains(&(x, y)) || (x, y) == grid_state.guard().position {
+                    continue;
+                }
+
+                for policy in [TurnPolicy::Right, TurnPolicy::Left, TurnPolicy::Alternate] {
+                    let gs = grid_state.with_policy(policy);
+                    let jumped = table.guard_loops(gs.guard(), Some((x, y)));
+
+                    let mut gs = gs.with_obstacle((x, y));
+                    let stepped = gs.step_until_end() == GuardState::InfiniteLoop;
+
+                    assert_eq!(stepped, jumped, "obstacle at {:?}, {:?}", (x, y), policy);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_turn_policies() {
+        let input = include_str!("../inputs/06.ex");
+        let grid_state = GridState::parse(input);
+
+        // Turning left at the first obstacle takes the guard straight out of the area
+        let mut gs = grid_state.with_policy(TurnPolicy::Left);
+        assert_eq!(gs.step_until_end(), GuardState::LeftArea);
+        assert_eq!(gs.count_visited(), 10);
+
+        // Turning right then left takes the guard out of the top after the second obstacle
+        let mut gs = grid_state.with_policy(TurnPolicy::Alternate);
+        assert_eq!(gs.step_until_end(), GuardState::LeftArea);
+        assert_eq!(gs.count_visited(), 11);
+        assert_eq!(gs.guard().position, (8, 0));
+
+        // Obstacles are only tried where the guard walks with the chosen policy
+        let (pt1, pt2) = run_inner(&grid_state.with_policy(TurnPolicy::Left)).unwrap();
+        assert_eq!(pt1, 10);
+        assert!(pt2.iter().all(|pos| grid_state
+            .with_policy(TurnPolicy::Left)
+            .loop_path(*pos)
+            .is_some()));
+
+        // A guard which loops on its own has no answer
+        let looping = GridState::parse(".#..\n...#\n#^..\n..#.");
+        assert!(run_inner(&looping).is_none());
+    }
+
+    #[test]
+    fn test_multiple_guards() {
+        let mut gs = GridState::parse(".....\n.>.<.\n..^..");
+        assert_eq!(gs.guards.len(), 3);
+        assert_eq!(gs.step_until_end(), GuardState::LeftArea);
+
+        // All three guards meet in the middle after the first step
+        let collision = |guards| Collision {
+            step: 1,
+            guards,
+            position: (2, 1),
+        };
+        assert_eq!(
+            gs.collisions,
+            vec![collision((0, 1)), collision((0, 2)), collision((1, 2))]
+        );
+
+        let visited: Vec<Vec<(usize, usize)>> = gs
+            .guards
+            .iter()
+            .map(|guard| guard.unique_positions().into_iter().sorted().collect())
+            .collect();
+        assert_eq!(visited[0], vec![(1, 1), (2, 1), (3, 1), (4, 1)]);
+        assert_eq!(visited[1], vec![(0, 1), (1, 1), (2, 1), (3, 1)]);
+        assert_eq!(visited[2], vec![(2, 0), (2, 1), (2, 2)]);
+        assert_eq!(gs.count_visited(), 7);
+    }
+
+    #[test]
+    fn test_guards_swapping() {
+        let mut gs = GridState::parse("..><..");
+        gs.step();
+        assert_eq!(
+            gs.collisions,
+            vec![Collision {
+                step: 1,
+                guards: (0, 1),
+                position: (3, 0),
+            }]
+        );
     }
 }
@@ -1,28 +1,50 @@
-use num::Integer;
-use std::time::Instant;
+use num::integer::{ExtendedGcd, Integer};
+use std::{cmp::Ordering, fmt::Write, time::Instant};
 
 pub fn run_outer() -> String {
+    run_outer_with(false)
+}
+
+/// Run the puzzle, and optionally follow the answers with a table of how each machine is won in
+/// both parts.
+pub fn run_outer_with(show_table: bool) -> String {
     let input = include_str!("../inputs/13.in");
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let (pt1, pt2) = run(input);
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
         pt1,
         pt2,
         elapsed.as_micros()
-    )
+    );
+
+    if show_table {
+        let machines = parse(input);
+        let rules_pt1 = Rules::default().with_press_limit(PT1_PRESS_LIMIT);
+        write!(out, "\npt1:\n{}", results_table(&machines, &rules_pt1)).unwrap();
+        let machines_pt2 = offset_prizes(&machines);
+        write!(
+            out,
+            "pt2:\n{}",
+            results_table(&machines_pt2, &Rules::default())
+        )
+        .unwrap();
+    }
+
+    out
 }
 
 const TOKENS_A: u64 = 3;
 const TOKENS_B: u64 = 1;
-const PT2_OFFSET: isize = 10000000000000;
-
-// Note that the answer for pt2 is quite sensitive to the floating point precision we decide on
-// here. Empirically, 0.001 seems about right (reducing it further doesn't change the answer).
-const FLOAT_EQUAL_MARGIN: f64 = 0.001;
+const PT1_PRESS_LIMIT: i128 = 100;
+const PT2_OFFSET: i128 = 10000000000000;
 
-type Xy = (isize, isize);
+// Everything is done in 128-bit integers so that the products in Cramer's rule and the extended
+// gcd, which multiply a prize coordinate by a button move, have plenty of headroom with
+// `PT2_OFFSET`. They are not unbounded though: with the puzzle's two or three digit button moves,
+// prizes beyond roughly 1e35 would overflow `i128`.
+type Xy = (i128, i128);
 
 #[derive(Debug, Clone, Default)]
 struct Machine {
@@ -43,69 +65,105 @@ impl From<&str> for Machine {
     }
 }
 
-impl Machine {
-    fn min_cost(&self) -> Option<u64> {
-        // First check if a and b have the same ratio of x:y. For example if they're both 1, 2 or
-        // a = 1, 2 and b = 2, 4 or something like that. In that case there is more than one
-        // solution for how the prize number can be made out of a and b.
-        if self.a.0 * self.b.1 == self.a.1 * self.b.0 {
-            // First try to make the prize from only b (as this is the cheapest way).
-            // If that doesn't work, incrementally add an a each time and try again.
-            let mut a_count = 0;
-            loop {
-                let p = (
-                    self.prize.0 - self.a.0 * a_count,
-                    self.prize.1 - self.a.1 * a_count,
-                );
-                let (quotient, remainder) = p.0.div_rem(&self.b.0);
-                if remainder == 0 && self.b.1 * quotient == p.1 {
-                    // We can get to the prize
-                    return Some(quotient as u64 * TOKENS_B + a_count as u64 * TOKENS_A);
-                }
-
-                if p.0 < self.a.0 || p.1 < self.a.1 {
-                    // Impossible to get to the prize
-                    return None;
-                }
-
-                a_count += 1;
-            }
+/// Token cost of each button, and optionally the maximum number of times each may be pressed.
+#[derive(Debug, Clone, Copy)]
+struct Rules {
+    tokens_a: u64,
+    tokens_b: u64,
+    limit_a: Option<i128>,
+    limit_b: Option<i128>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            tokens_a: TOKENS_A,
+            tokens_b: TOKENS_B,
+            limit_a: None,
+            limit_b: None,
         }
+    }
+}
+
+impl Rules {
+    fn with_press_limit(self, limit: i128) -> Self {
+        Self {
+            limit_a: Some(limit),
+            limit_b: Some(limit),
+            ..self
+        }
+    }
+
+    fn cost(&self, n: i128, m: i128) -> u128 {
+        self.tokens_a as u128 * n as u128 + self.tokens_b as u128 * m as u128
+    }
+}
+
+/// The cheapest way to win the prize on a single machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Win {
+    a_presses: i128,
+    b_presses: i128,
+    tokens: u128,
+}
+
+impl Machine {
+    fn min_cost(&self, rules: &Rules) -> Option<u128> {
+        self.win(rules).map(|win| win.tokens)
+    }
 
+    fn win(&self, rules: &Rules) -> Option<Win> {
+        let (n, m) = self.min_presses(rules)?;
+        Some(Win {
+            a_presses: n,
+            b_presses: m,
+            tokens: rules.cost(n, m),
+        })
+    }
+
+    /// Find the cheapest number of presses (n, m) of buttons a and b which reach the prize.
+    fn min_presses(&self, rules: &Rules) -> Option<(i128, i128)> {
         //
         // (1): xp = xa * n + xb * m
         // (2): yp = ya * n + yb * m
         //
-        // We know xp, xp, xb, yp, ya, yb so solve for n and m
+        // We know xp, xp, xb, yp, ya, yb so solve for n and m using Cramer's rule:
         //
-        // rearrange (1): (xp - xb * m) / xa = n
-        // sub into (2):  yp = ya * (xp - xb * m) / xa + yb * m
-        //                yp = ya * xp / xa - ya * xb * m / xa + yb * m
-        //                yp - ya * xp / xa = m * (yb - ya * xb / xa)
-        //                m = (yp - ya * xp / xa) / (yb - ya * xb / xa)
+        //   n = (xp * yb - yp * xb) / det
+        //   m = (xa * yp - ya * xp) / det
+        //   det = xa * yb - ya * xb
         //
-        // Since the equations above involve division it's a pain to use integer math.
-        // So instead give it a go with f64, and confirm result is right using integer math
-        let xp = self.prize.0 as f64;
-        let yp = self.prize.1 as f64;
-        let xa = self.a.0 as f64;
-        let ya = self.a.1 as f64;
-        let xb = self.b.0 as f64;
-        let yb = self.b.1 as f64;
-
-        let m = (yp - ya * xp / xa) / (yb - ya * xb / xa);
-        if !float_approx_integer(m) {
-            return None;
-        }
+        // The solution is only valid if both divisions are exact and non-negative.
+        let det = self.a.0 * self.b.1 - self.a.1 * self.b.0;
+
+        let (n, m) = if det != 0 {
+            let n_num = self.prize.0 * self.b.1 - self.prize.1 * self.b.0;
+            let m_num = self.a.0 * self.prize.1 - self.a.1 * self.prize.0;
+            if n_num % det != 0 || m_num % det != 0 {
+                return None;
+            }
+
+            (n_num / det, m_num / det)
+        } else {
+            // a and b have the same ratio of x:y. For example if they're both 1, 2 or a = 1, 2
+            // and b = 2, 4 or something like that. In that case there may be more than one
+            // solution for how the prize number can be made out of a and b, and we have to pick
+            // the cheapest along a single axis. Any axis which moves will do, as long as we
+            // check the other one afterwards.
+            if self.a.0 != 0 || self.b.0 != 0 {
+                min_presses_1d(self.a.0, self.b.0, self.prize.0, rules)?
+            } else {
+                min_presses_1d(self.a.1, self.b.1, self.prize.1, rules)?
+            }
+        };
 
-        let n = (xp - xb * m) / xa;
-        if !float_approx_integer(n) {
+        if n < 0 || m < 0 {
             return None;
         }
 
-        let m = m.round() as isize;
-        let n = n.round() as isize;
-        if m < 0 || n < 0 {
+        if rules.limit_a.is_some_and(|limit| n > limit)
+            || rules.limit_b.is_some_and(|limit| m > limit)
+        {
             return None;
         }
 
@@ -115,7 +173,77 @@ impl Machine {
             return None;
         }
 
-        return Some(TOKENS_B * m as u64 + TOKENS_A * n as u64);
+        Some((n, m))
+    }
+}
+
+/// Find the cheapest non-negative n, m within the press limits such that `a * n + b * m == p`.
+///
+/// Uses the extended gcd to find one solution, then walks along the family of solutions
+/// (n + k * b / g, m - k * a / g) to whichever end of the valid range is cheapest, since the cost
+/// is linear in k.
+fn min_presses_1d(a: i128, b: i128, p: i128, rules: &Rules) -> Option<(i128, i128)> {
+    if a == 0 && b == 0 {
+        return if p == 0 { Some((0, 0)) } else { None };
+    }
+
+    if a == 0 {
+        return if p % b == 0 { Some((0, p / b)) } else { None };
+    }
+
+    if b == 0 {
+        return if p % a == 0 { Some((p / a, 0)) } else { None };
+    }
+
+    let ExtendedGcd { gcd, x, y } = a.extended_gcd(&b);
+    if p % gcd != 0 {
+        return None;
+    }
+
+    let n0 = x * (p / gcd);
+    let m0 = y * (p / gcd);
+    let step_n = b / gcd;
+    let step_m = a / gcd;
+
+    // Each constraint has the form c + k * s >= 0
+    let mut constraints = vec![(n0, step_n), (m0, -step_m)];
+    if let Some(limit) = rules.limit_a {
+        constraints.push((limit - n0, -step_n));
+    }
+    if let Some(limit) = rules.limit_b {
+        constraints.push((limit - m0, step_m));
+    }
+
+    let (k_min, k_max) = k_range(&constraints)?;
+
+    let slope = rules.tokens_a as i128 * step_n - rules.tokens_b as i128 * step_m;
+    let k = if slope >= 0 { k_min } else { k_max };
+    if k == i128::MIN || k == i128::MAX {
+        // The cheapest direction is unbounded, which can only happen with negative buttons
+        return None;
+    }
+
+    Some((n0 + k * step_n, m0 - k * step_m))
+}
+
+/// Range of k for which every constraint `c + k * s` is non-negative.
+fn k_range(constraints: &[(i128, i128)]) -> Option<(i128, i128)> {
+    let mut k_min = i128::MIN;
+    let mut k_max = i128::MAX;
+
+    for (c, s) in constraints.iter().copied() {
+        match s.cmp(&0) {
+            Ordering::Greater => k_min = k_min.max(-Integer::div_floor(&c, &s)),
+            Ordering::Less => k_max = k_max.min(Integer::div_floor(&c, &-s)),
+            Ordering::Equal if c < 0 => return None,
+            Ordering::Equal => {}
+        }
+    }
+
+    if k_min > k_max {
+        None
+    } else {
+        Some((k_min, k_max))
     }
 }
 
@@ -125,33 +253,60 @@ fn line_to_xy(line: &str, offset: usize, separator: &str) -> Xy {
     (x.parse().unwrap(), y.parse().unwrap())
 }
 
-fn float_approx_integer(n: f64) -> bool {
-    if (n - n.round()).abs() < FLOAT_EQUAL_MARGIN {
-        return true;
+/// Render a table of how each machine's prize is won, for auditing individual machines.
+fn results_table(machines: &[Machine], rules: &Rules) -> String {
+    let mut out = format!(
+        "{:>7} | {:>15} | {:>15} | {:>16}\n",
+        "machine", "A presses", "B presses", "tokens"
+    );
+
+    for (i, machine) in machines.iter().enumerate() {
+        match machine.win(rules) {
+            Some(win) => writeln!(
+                out,
+                "{:>7} | {:>15} | {:>15} | {:>16}",
+                i + 1,
+                win.a_presses,
+                win.b_presses,
+                win.tokens
+            ),
+            None => writeln!(out, "{:>7} | unreachable", i + 1),
+        }
+        .unwrap();
     }
 
-    false
+    out
 }
 
-fn run(input: &str) -> (u64, u64) {
-    let machines: Vec<Machine> = input
-        .trim()
-        .split("\n\n")
-        .map(|m| Machine::from(m))
-        .collect();
-
-    let pt1: u64 = machines.iter().filter_map(|m| m.min_cost()).sum();
+fn parse(input: &str) -> Vec<Machine> {
+    input.trim().split("\n\n").map(Machine::from).collect()
+}
 
-    let machines_pt2: Vec<Machine> = machines
+/// The machines for part 2, with `PT2_OFFSET` added to each prize.
+fn offset_prizes(machines: &[Machine]) -> Vec<Machine> {
+    machines
         .iter()
         .map(|m| {
             let mut machine = m.clone();
             machine.prize = (machine.prize.0 + PT2_OFFSET, machine.prize.1 + PT2_OFFSET);
             machine
         })
-        .collect();
+        .collect()
+}
+
+fn run(input: &str) -> (u128, u128) {
+    let machines = parse(input);
+
+    let rules_pt1 = Rules::default().with_press_limit(PT1_PRESS_LIMIT);
+    let pt1: u128 = machines.iter().filter_map(|m| m.min_cost(&rules_pt1)).sum();
 
-    let pt2: u64 = machines_pt2.iter().filter_map(|m| m.min_cost()).sum();
+    let machines_pt2 = offset_prizes(&machines);
+
+    let rules_pt2 = Rules::default();
+    let pt2: u128 = machines_pt2
+        .iter()
+        .filter_map(|m| m.min_cost(&rules_pt2))
+        .sum();
 
     (pt1, pt2)
 }
@@ -165,5 +320,154 @@ mod test {
         let input = include_str!("../inputs/13.ex");
         let (pt1, pt2) = run(&input);
         assert_eq!(pt1, 480);
+        assert_eq!(pt2, 875318608908);
+    }
+
+    #[test]
+    fn test_min_presses() {
+        let machine = Machine {
+            a: (94, 34),
+            b: (22, 67),
+            prize: (8400, 5400),
+        };
+        assert_eq!(machine.min_presses(&Rules::default()), Some((80, 40)));
+        assert_eq!(machine.min_cost(&Rules::default()), Some(280));
+
+        let machine = Machine {
+            a: (26, 66),
+            b: (67, 21),
+            prize: (12748, 12176),
+        };
+        assert_eq!(machine.min_presses(&Rules::default()), None);
+    }
+
+    #[test]
+    fn test_min_presses_collinear() {
+        // b is cheaper per unit distance, but 7 can't be reached with b alone
+        let machine = Machine {
+            a: (1, 2),
+            b: (2, 4),
+            prize: (7, 14),
+        };
+        assert_eq!(machine.min_presses(&Rules::default()), Some((1, 3)));
+
+        // a is cheaper per unit distance here, since it goes 4x as far for 3x the tokens
+        let machine = Machine {
+            a: (4, 4),
+            b: (1, 1),
+            prize: (10, 10),
+        };
+        assert_eq!(machine.min_presses(&Rules::default()), Some((2, 2)));
+
+        let machine = Machine {
+            a: (2, 4),
+            b: (4, 8),
+            prize: (7, 14),
+        };
+        assert_eq!(machine.min_presses(&Rules::default()), None);
+
+        let machine = Machine {
+            a: (1, 2),
+            b: (2, 4),
+            prize: (7, 15),
+        };
+        assert_eq!(machine.min_presses(&Rules::default()), None);
+
+        let machine = Machine {
+            a: (0, 3),
+            b: (0, 5),
+            prize: (0, 11),
+        };
+        assert_eq!(machine.min_presses(&Rules::default()), Some((2, 1)));
+    }
+
+    #[test]
+    fn test_min_presses_large_offset() {
+        let offset = 10i128.pow(30);
+        let machine = Machine {
+            a: (3, 1),
+            b: (1, 3),
+            prize: (4 * offset, 4 * offset),
+        };
+        assert_eq!(
+            machine.min_presses(&Rules::default()),
+            Some((offset, offset))
+        );
+        assert_eq!(
+            machine.min_cost(&Rules::default()),
+            Some(4 * offset as u128)
+        );
+    }
+
+    #[test]
+    fn test_press_limits() {
+        let machine = Machine {
+            a: (94, 34),
+            b: (22, 67),
+            prize: (8400, 5400),
+        };
+        let rules = Rules::default().with_press_limit(80);
+        assert_eq!(machine.min_presses(&rules), Some((80, 40)));
+        let rules = Rules::default().with_press_limit(79);
+        assert_eq!(machine.min_presses(&rules), None);
+
+        // Without a limit the cheapest option is all b presses, but only 5 are allowed
+        let machine = Machine {
+            a: (2, 2),
+            b: (1, 1),
+            prize: (10, 10),
+        };
+        assert_eq!(machine.min_presses(&Rules::default()), Some((0, 10)));
+        let rules = Rules {
+            limit_b: Some(5),
+            ..Rules::default()
+        };
+        assert_eq!(machine.min_presses(&rules), Some((3, 4)));
+        let rules = Rules {
+            limit_a: Some(2),
+            limit_b: Some(5),
+            ..Rules::default()
+        };
+        assert_eq!(machine.min_presses(&rules), None);
+    }
+
+    #[test]
+    fn test_custom_tokens() {
+        let machine = Machine {
+            a: (2, 2),
+            b: (1, 1),
+            prize: (10, 10),
+        };
+        let rules = Rules {
+            tokens_a: 1,
+            tokens_b: 1,
+            ..Rules::default()
+        };
+        assert_eq!(
+            machine.win(&rules),
+            Some(Win {
+                a_presses: 5,
+                b_presses: 0,
+                tokens: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_results_table() {
+        let input = include_str!("../inputs/13.ex");
+        let machines = parse(input);
+        let table = results_table(&machines, &Rules::default());
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[1],
+            "      1 |              80 |              40 |              280"
+        );
+        assert_eq!(lines[2], "      2 | unreachable");
+        assert_eq!(
+            lines[3],
+            "      3 |              38 |              86 |              200"
+        );
     }
 }
@@ -1,16 +1,60 @@
-use std::{collections::HashSet, time::Instant};
+use std::{collections::HashSet, fmt::Write, fs, io, ops::Range, path::Path, time::Instant};
+
+use itertools::Itertools;
+use num::integer::{ExtendedGcd, Integer};
+
+const STEPS: isize = 100;
+const BOUNDS: (isize, isize) = (101, 103);
 
 pub fn run_outer() -> String {
+    run_outer_with(None, None)
+}
+
+/// Run the puzzle, and optionally write images to `dir`: the picture found in part 2 as
+/// `picture.pgm`, and a PBM frame for every step in `frames`.
+pub fn run_outer_with(dir: Option<&Path>, frames: Option<Range<isize>>) -> String {
     let input = include_str!("../inputs/14.in");
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let (pt1, pt2) = run(input);
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
         pt1,
-        pt2,
+        pt2.map_or("none".to_string(), |step| step.to_string()),
         elapsed.as_micros()
-    )
+    );
+
+    if let Some(dir) = dir {
+        match write_images(input, pt2, frames, dir) {
+            Ok(()) => write!(out, "\nwrote images to {}", dir.display()).unwrap(),
+            Err(e) => write!(out, "\nfailed to write images: {e}").unwrap(),
+        }
+    }
+
+    out
+}
+
+fn parse(input: &str) -> Vec<Robot> {
+    input.trim().lines().map(Robot::from).collect()
+}
+
+fn write_images(
+    input: &str,
+    picture: Option<u64>,
+    frames: Option<Range<isize>>,
+    dir: &Path,
+) -> io::Result<()> {
+    let robots = parse(input);
+    fs::create_dir_all(dir)?;
+
+    if let Some(step) = picture {
+        write_pgm(&robots, step as isize, BOUNDS, dir.join("picture.pgm"))?;
+    }
+    if let Some(steps) = frames {
+        write_frames(&robots, steps, BOUNDS, FrameFormat::Pbm, dir)?;
+    }
+
+    Ok(())
 }
 
 #[derive(Debug, Clone)]
@@ -46,7 +90,7 @@ impl Robot {
 }
 
 fn count_positions_in_range(
-    positions: &Vec<(isize, isize)>,
+    positions: &[(isize, isize)],
     min_bound: (isize, isize),
     max_bound: (isize, isize),
 ) -> u64 {
@@ -66,84 +110,235 @@ fn count_positions_in_range(
         .sum()
 }
 
-#[allow(unused)]
-fn display_robots(positions: &Vec<(isize, isize)>) {
-    let mut minx = isize::MAX;
-    let mut miny = isize::MAX;
-    let mut maxx = 0;
-    let mut maxy = 0;
-
-    for pos in positions.iter() {
-        if pos.0 < minx {
-            minx = pos.0;
-        }
-        if pos.0 > maxx {
-            maxx = pos.0;
-        }
-        if pos.1 < miny {
-            miny = pos.1;
-        }
-        if pos.1 > maxy {
-            maxy = pos.1;
-        }
-    }
-
-    for y in miny..maxy + 1 {
-        for x in minx..maxx + 1 {
-            if positions.iter().any(|pos| pos.0 == x && pos.1 == y) {
-                print!("#");
-            } else {
-                print!(" ");
-            }
-        }
-        print!("\n");
-    }
+/// Summary of where the robots are at a single step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StepStats {
+    /// Robots in each quadrant, ignoring those on the middle row or column. Ordered top left, top
+    /// right, bottom left, bottom right.
+    quadrants: [u64; 4],
+    /// Number of tiles with more than one robot on them.
+    overlaps: u64,
+    /// Smallest and largest (x, y) of any robot.
+    bounding_box: ((isize, isize), (isize, isize)),
 }
 
-fn run_inner(input: &str, steps: isize, bounds: (isize, isize)) -> (u64, u64) {
-    let robots: Vec<Robot> = input.trim().lines().map(|line| Robot::from(line)).collect();
-
-    let end_positions: Vec<(isize, isize)> =
-        robots.iter().map(|r| r.step_by(steps, bounds)).collect();
+fn step_stats(robots: &[Robot], step: isize, bounds: (isize, isize)) -> StepStats {
+    let positions: Vec<(isize, isize)> = robots.iter().map(|r| r.step_by(step, bounds)).collect();
 
     let midpoint_x = bounds.0 / 2;
     let midpoint_y = bounds.1 / 2;
 
-    let quadrant_counts = [
-        count_positions_in_range(&end_positions, (0, 0), (midpoint_x, midpoint_y)),
-        count_positions_in_range(&end_positions, (midpoint_x + 1, 0), (bounds.0, midpoint_y)),
-        count_positions_in_range(&end_positions, (0, midpoint_y + 1), (midpoint_x, bounds.1)),
+    let quadrants = [
+        count_positions_in_range(&positions, (0, 0), (midpoint_x, midpoint_y)),
+        count_positions_in_range(&positions, (midpoint_x + 1, 0), (bounds.0, midpoint_y)),
+        count_positions_in_range(&positions, (0, midpoint_y + 1), (midpoint_x, bounds.1)),
         count_positions_in_range(
-            &end_positions,
+            &positions,
             (midpoint_x + 1, midpoint_y + 1),
             (bounds.0, bounds.1),
         ),
     ];
 
-    let pt1: u64 = quadrant_counts.iter().product();
-
-    // Part 2 is a weird one. We don't actually know the image that we are looking for. There's
-    // a variety of heuristics that we could use, but ultimately we have to print out the layout
-    // and confirm if we were right or not. It's probably fair to assume that the image won't have
-    // overlapping robots since these would not add to the image. So try that first.
-    let mut pt2 = 0u64;
-    for i in 1.. {
-        let end_positions: Vec<(isize, isize)> =
-            robots.iter().map(|r| r.step_by(i, bounds)).collect();
-
-        if end_positions.len() == HashSet::<&(isize, isize)>::from_iter(end_positions.iter()).len()
-        {
-            // display_robots(&end_positions);
-            pt2 = i as u64;
-            break;
+    let overlaps = positions
+        .iter()
+        .counts()
+        .values()
+        .filter(|n| **n > 1)
+        .count() as u64;
+
+    let (min_x, max_x) = positions
+        .iter()
+        .map(|pos| pos.0)
+        .minmax()
+        .into_option()
+        .unwrap_or((0, 0));
+    let (min_y, max_y) = positions
+        .iter()
+        .map(|pos| pos.1)
+        .minmax()
+        .into_option()
+        .unwrap_or((0, 0));
+
+    StepStats {
+        quadrants,
+        overlaps,
+        bounding_box: ((min_x, min_y), (max_x, max_y)),
+    }
+}
+
+/// Variance of the robot positions along one axis, scaled by n^2 to stay in integers.
+fn scaled_variance(positions: &[(isize, isize)], axis: fn(&(isize, isize)) -> isize) -> isize {
+    let n = positions.len() as isize;
+    let sum: isize = positions.iter().map(axis).sum();
+    let sum_sq: isize = positions.iter().map(|pos| axis(pos) * axis(pos)).sum();
+    n * sum_sq - sum * sum
+}
+
+/// Solve t = a1 (mod m1), t = a2 (mod m2) for the smallest non-negative t.
+fn crt(a1: isize, m1: isize, a2: isize, m2: isize) -> Option<isize> {
+    let ExtendedGcd { gcd, x, .. } = m1.extended_gcd(&m2);
+    if (a2 - a1) % gcd != 0 {
+        return None;
+    }
+
+    let lcm = m1 / gcd * m2;
+    let k = ((a2 - a1) / gcd * x).rem_euclid(m2 / gcd);
+    Some((a1 + m1 * k).rem_euclid(lcm))
+}
+
+/// Find the step at which the robots form a picture.
+///
+/// The robots' x positions repeat every `bounds.0` steps and their y positions every `bounds.1`
+/// steps. When the picture appears the robots are clustered together, so the variance along each
+/// axis is at a minimum. Find the step within each period with the lowest variance along that
+/// axis, then combine the two with the Chinese remainder theorem.
+fn find_picture(robots: &[Robot], bounds: (isize, isize)) -> Option<isize> {
+    let best_step = |period: isize, axis: fn(&(isize, isize)) -> isize| {
+        (0..period)
+            .min_by_key(|i| {
+                let positions: Vec<(isize, isize)> =
+                    robots.iter().map(|r| r.step_by(*i, bounds)).collect();
+                scaled_variance(&positions, axis)
+            })
+            .unwrap()
+    };
+
+    let tx = best_step(bounds.0, |pos| pos.0);
+    let ty = best_step(bounds.1, |pos| pos.1);
+
+    crt(tx, bounds.0, ty, bounds.1)
+}
+
+/// Render robot positions as a plain (ASCII) PGM image, with brighter pixels where more robots
+/// overlap.
+fn to_pgm(positions: &[(isize, isize)], bounds: (isize, isize)) -> String {
+    let mut counts = vec![vec![0usize; bounds.0 as usize]; bounds.1 as usize];
+    for pos in positions.iter() {
+        counts[pos.1 as usize][pos.0 as usize] += 1;
+    }
+
+    let max = counts.iter().flatten().copied().max().unwrap_or(0).max(1);
+
+    let mut out = format!("P2\n{} {}\n{}\n", bounds.0, bounds.1, max);
+    for row in counts.iter() {
+        out.push_str(&row.iter().join(" "));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Write the robot positions at a given step to a PGM image file.
+fn write_pgm(
+    robots: &[Robot],
+    step: isize,
+    bounds: (isize, isize),
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let positions: Vec<(isize, isize)> = robots.iter().map(|r| r.step_by(step, bounds)).collect();
+    fs::write(path, to_pgm(&positions, bounds))
+}
+
+#[allow(unused)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameFormat {
+    /// `#` for a robot and `.` for an empty tile
+    Text,
+    /// Plain (ASCII) PBM image
+    Pbm,
+}
+
+impl FrameFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Pbm => "pbm",
         }
     }
+}
+
+fn render_frame(
+    positions: &[(isize, isize)],
+    bounds: (isize, isize),
+    format: FrameFormat,
+) -> String {
+    let occupied: HashSet<&(isize, isize)> = positions.iter().collect();
+
+    let mut out = match format {
+        FrameFormat::Text => String::new(),
+        FrameFormat::Pbm => format!("P1\n{} {}\n", bounds.0, bounds.1),
+    };
+
+    for y in 0..bounds.1 {
+        let mut row = (0..bounds.0).map(|x| match (format, occupied.contains(&(x, y))) {
+            (FrameFormat::Text, true) => "#",
+            (FrameFormat::Text, false) => ".",
+            (FrameFormat::Pbm, true) => "1",
+            (FrameFormat::Pbm, false) => "0",
+        });
+
+        match format {
+            FrameFormat::Text => out.extend(row),
+            FrameFormat::Pbm => out.push_str(&row.join(" ")),
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Render a frame for every step in `steps`.
+fn frames(
+    robots: &[Robot],
+    steps: Range<isize>,
+    bounds: (isize, isize),
+    format: FrameFormat,
+) -> impl Iterator<Item = (isize, String)> + '_ {
+    steps.map(move |step| {
+        let positions: Vec<(isize, isize)> =
+            robots.iter().map(|r| r.step_by(step, bounds)).collect();
+        (step, render_frame(&positions, bounds, format))
+    })
+}
+
+/// Write one file per step in `steps` to `dir`, named by step number, e.g. `step_00042.pbm`.
+fn write_frames(
+    robots: &[Robot],
+    steps: Range<isize>,
+    bounds: (isize, isize),
+    format: FrameFormat,
+    dir: impl AsRef<Path>,
+) -> io::Result<()> {
+    fs::create_dir_all(&dir)?;
+
+    for (step, frame) in frames(robots, steps, bounds, format) {
+        let path = dir
+            .as_ref()
+            .join(format!("step_{:05}.{}", step, format.extension()));
+        fs::write(path, frame)?;
+    }
+
+    Ok(())
+}
+
+fn run_inner(input: &str, steps: isize, bounds: (isize, isize)) -> (u64, Option<u64>) {
+    let robots = parse(input);
+
+    let pt1: u64 = step_stats(&robots, steps, bounds)
+        .quadrants
+        .iter()
+        .product();
+
+    // Part 2 is a weird one. We don't actually know the image that we are looking for, only that
+    // the robots will be bunched up together to draw it. If the bounds aren't coprime, the
+    // clustering along each axis may never line up.
+    let pt2 = find_picture(&robots, bounds).map(|step| step as u64);
 
     (pt1, pt2)
 }
 
-fn run(input: &str) -> (u64, u64) {
-    run_inner(input, 100, (101, 103))
+fn run(input: &str) -> (u64, Option<u64>) {
+    run_inner(input, STEPS, BOUNDS)
 }
 
 #[cfg(test)]
@@ -155,7 +350,101 @@ mod test {
         let input = include_str!("../inputs/14.ex");
         let (pt1, pt2) = run_inner(&input, 100, (11, 7));
         assert_eq!(pt1, 12);
-        assert_eq!(pt2, 0);
+
+        // The example doesn't contain a picture, but pt2 should still be the step where the
+        // robots are most tightly clustered along both axes.
+        let robots: Vec<Robot> = input.trim().lines().map(Robot::from).collect();
+        let spread = |i: isize| {
+            let positions: Vec<(isize, isize)> =
+                robots.iter().map(|r| r.step_by(i, (11, 7))).collect();
+            (
+                scaled_variance(&positions, |pos| pos.0),
+                scaled_variance(&positions, |pos| pos.1),
+            )
+        };
+        let (best_x, best_y) = spread(pt2.unwrap() as isize);
+        for i in 0..77 {
+            let (x, y) = spread(i);
+            assert!(x >= best_x && y >= best_y);
+        }
+    }
+
+    #[test]
+    fn test_find_picture() {
+        // Draw a small solid square at step 1234, then run each robot backwards to get the start
+        let bounds = (101, 103);
+        let robots: Vec<Robot> = (0..100)
+            .map(|i| {
+                let velocity = ((i * 7) % 11 - 5, (i * 13) % 17 - 8);
+                let tree = Robot {
+                    position: (40 + i % 10, 50 + i / 10),
+                    velocity: (-velocity.0, -velocity.1),
+                };
+                Robot {
+                    position: tree.step_by(1234, bounds),
+                    velocity,
+                }
+            })
+            .collect();
+
+        assert_eq!(find_picture(&robots, bounds), Some(1234));
+    }
+
+    #[test]
+    fn test_step_stats() {
+        let input = include_str!("../inputs/14.ex");
+        let robots: Vec<Robot> = input.trim().lines().map(Robot::from).collect();
+
+        let stats = step_stats(&robots, 100, (11, 7));
+        assert_eq!(stats.quadrants, [1, 3, 4, 1]);
+        assert_eq!(stats.overlaps, 2);
+        assert_eq!(stats.bounding_box, ((0, 0), (9, 6)));
+
+        let robots = vec![
+            Robot {
+                position: (1, 1),
+                velocity: (1, 0),
+            },
+            Robot {
+                position: (3, 4),
+                velocity: (0, 1),
+            },
+        ];
+        let stats = step_stats(&robots, 2, (5, 5));
+        assert_eq!(stats.quadrants, [0, 2, 0, 0]);
+        assert_eq!(stats.overlaps, 1);
+        assert_eq!(stats.bounding_box, ((3, 1), (3, 1)));
+    }
+
+    #[test]
+    fn test_frames() {
+        let robots = vec![Robot {
+            position: (0, 0),
+            velocity: (1, 1),
+        }];
+
+        let text: Vec<(isize, String)> = frames(&robots, 2..4, (3, 2), FrameFormat::Text).collect();
+        assert_eq!(
+            text,
+            vec![(2, "..#\n...\n".to_string()), (3, "...\n#..\n".to_string())]
+        );
+
+        let pbm: Vec<(isize, String)> = frames(&robots, 1..2, (3, 2), FrameFormat::Pbm).collect();
+        assert_eq!(pbm, vec![(1, "P1\n3 2\n0 0 0\n0 1 0\n".to_string())]);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(2, 3, 3, 5), Some(8));
+        assert_eq!(crt(0, 101, 0, 103), Some(0));
+        assert_eq!(crt(100, 101, 102, 103), Some(101 * 103 - 1));
+        assert_eq!(crt(1, 4, 2, 6), None);
+    }
+
+    #[test]
+    fn test_to_pgm() {
+        let pgm = to_pgm(&[(0, 0), (2, 1), (2, 1)], (3, 2));
+        assert_eq!(pgm, "P2\n3 2\n2\n1 0 0\n0 0 2\n");
     }
 
     #[test]
@@ -167,4 +456,50 @@ mod test {
         let end_pos = robot.step_by(5, (11, 7));
         assert_eq!(end_pos, (1, 3));
     }
+
+    #[test]
+    fn test_no_picture() {
+        // The robots line up at step 3 (mod 4) along x and step 0 (mod 6) along y, which can never
+        // happen at the same time
+        let input = "p=0,0 v=0,0\np=1,0 v=1,1";
+        let (_pt1, pt2) = run_inner(input, 1, (4, 6));
+        assert_eq!(pt2, None);
+    }
+
+    #[test]
+    fn test_write_images() {
+        let robots = vec![Robot {
+            position: (0, 0),
+            velocity: (1, 1),
+        }];
+        let dir = std::env::temp_dir().join("aoc2024_day14_write_images");
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        write_pgm(&robots, 1, (3, 2), dir.join("picture.pgm")).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("picture.pgm")).unwrap(),
+            "P2\n3 2\n1\n0 0 0\n0 1 0\n"
+        );
+
+        write_frames(&robots, 2..4, (3, 2), FrameFormat::Text, &dir).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("step_00002.txt")).unwrap(),
+            "..#\n...\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("step_00003.txt")).unwrap(),
+            "...\n#..\n"
+        );
+        assert!(!dir.join("step_00004.txt").exists());
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Writing everything for the CLI creates the directory as needed
+        let input = include_str!("../inputs/14.ex");
+        write_images(input, Some(5), Some(0..2), &dir).unwrap();
+        assert!(dir.join("picture.pgm").exists());
+        assert!(dir.join("step_00000.pbm").exists());
+        assert!(dir.join("step_00001.pbm").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,16 +1,44 @@
-use std::{fmt::Display, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Write},
+    fs, io,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
 
 pub fn run_outer() -> String {
+    run_outer_with(None, None)
+}
+
+/// Run the puzzle, and optionally replay the moves: writing every frame of both parts to `dir`
+/// (under `pt1` and `pt2`), and animating the part 2 warehouse in the terminal with `delay`
+/// between frames.
+pub fn run_outer_with(dir: Option<&Path>, delay: Option<Duration>) -> String {
     let input = include_str!("../inputs/15.in");
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let (pt1, pt2) = run(input);
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
         pt1,
         pt2,
         elapsed.as_micros()
-    )
+    );
+
+    if let Some(dir) = dir {
+        match write_replays(input, dir) {
+            Ok(()) => write!(out, "\nwrote frames to {}", dir.display()).unwrap(),
+            Err(e) => write!(out, "\nfailed to write frames: {e}").unwrap(),
+        }
+    }
+
+    if let Some(delay) = delay {
+        let (grid, moves) = parse(input);
+        Replay::new(grid.expanded(), moves).animate(delay);
+    }
+
+    out
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -42,37 +70,19 @@ impl TryFrom<char> for Move {
 enum GridElement {
     Wall,
     Free,
-    Box,
     Robot,
-    BoxL,
-    BoxR,
+    /// Part of a box. Boxes can be any size, so every cell covered by the same box holds the same
+    /// id.
+    Box(usize),
 }
 
-impl TryFrom<char> for GridElement {
-    type Error = MyError;
-
-    fn try_from(value: char) -> Result<Self, Self::Error> {
-        match value {
-            '#' => Ok(GridElement::Wall),
-            '.' => Ok(GridElement::Free),
-            'O' => Ok(GridElement::Box),
-            '@' => Ok(GridElement::Robot),
-            '[' => Ok(GridElement::BoxL),
-            ']' => Ok(GridElement::BoxR),
-            _ => Err(MyError),
-        }
-    }
-}
-
-impl Into<char> for GridElement {
-    fn into(self) -> char {
+impl Move {
+    fn step(&self, (x, y): (usize, usize)) -> (usize, usize) {
         match self {
-            GridElement::Wall => '#',
-            GridElement::Free => '.',
-            GridElement::Robot => '@',
-            GridElement::Box => 'O',
-            GridElement::BoxL => '[',
-            GridElement::BoxR => ']',
+            Move::Up => (x, y - 1),
+            Move::Down => (x, y + 1),
+            Move::Right => (x + 1, y),
+            Move::Left => (x - 1, y),
         }
     }
 }
@@ -84,26 +94,38 @@ struct Grid {
 }
 
 impl From<&str> for Grid {
+    /// Parse a grid where `O` is a single-cell box, and wider boxes are written as `[`, followed by
+    /// any number of `=`, followed by `]`. Taller boxes are written with their top row as above,
+    /// and a `|` in every cell below it, so two boxes stacked on top of each other stay separate.
     fn from(s: &str) -> Self {
         let mut robot_loc = (0usize, 0usize);
-
-        let elements: Vec<Vec<GridElement>> = s
-            .trim()
-            .lines()
-            .enumerate()
-            .map(|(y, line)| {
-                line.chars()
-                    .enumerate()
-                    .map(|(x, ch)| {
-                        let ge = GridElement::try_from(ch).unwrap();
-                        if ge == GridElement::Robot {
-                            robot_loc = (x, y);
-                        }
-                        ge
-                    })
-                    .collect()
-            })
-            .collect();
+        let mut next_box = 0;
+        let mut elements: Vec<Vec<GridElement>> = Vec::new();
+
+        for (y, line) in s.trim().lines().enumerate() {
+            let mut row = Vec::new();
+            for (x, ch) in line.chars().enumerate() {
+                row.push(match ch {
+                    '#' => GridElement::Wall,
+                    '.' => GridElement::Free,
+                    '@' => {
+                        robot_loc = (x, y);
+                        GridElement::Robot
+                    }
+                    'O' | '[' => {
+                        next_box += 1;
+                        GridElement::Box(next_box - 1)
+                    }
+                    '=' | ']' => GridElement::Box(next_box - 1),
+                    '|' => match y.checked_sub(1).and_then(|y| elements[y].get(x)) {
+                        Some(GridElement::Box(id)) => GridElement::Box(*id),
+                        _ => panic!("box continued from nothing at ({x}, {y})"),
+                    },
+                    _ => panic!("invalid grid element {ch}"),
+                });
+            }
+            elements.push(row);
+        }
 
         Grid {
             elements,
@@ -114,11 +136,11 @@ impl From<&str> for Grid {
 
 impl Display for Grid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for line in self.elements.iter() {
-            for ge in line.iter() {
-                write!(f, "{}", Into::<char>::into(*ge))?
+        for (y, line) in self.elements.iter().enumerate() {
+            for x in 0..line.len() {
+                write!(f, "{}", self.element_char((x, y)))?
             }
-            write!(f, "\n")?
+            writeln!(f)?
         }
 
         Ok(())
@@ -126,102 +148,321 @@ impl Display for Grid {
 }
 
 impl Grid {
-    fn apply_move_inner(&mut self, (x, y): (usize, usize), mv: Move) -> Result<(), MyError> {
-        let (nx, ny) = match mv {
-            Move::Up => (x, y - 1),
-            Move::Down => (x, y + 1),
-            Move::Right => (x + 1, y),
-            Move::Left => (x - 1, y),
+    fn get(&self, (x, y): (usize, usize)) -> Option<GridElement> {
+        self.elements.get(y)?.get(x).copied()
+    }
+
+    /// Whether the neighbour of `pos` in the direction of `mv` is part of the same box.
+    fn same_box(&self, pos: (usize, usize), mv: Move) -> bool {
+        let neighbor = match mv {
+            Move::Up if pos.1 == 0 => return false,
+            Move::Left if pos.0 == 0 => return false,
+            _ => mv.step(pos),
         };
 
-        let current_element = self.elements[y][x];
-        let next_element = self.elements[ny][nx];
-
-        let res = match (next_element, mv) {
-            (GridElement::Wall, _) => return Err(MyError),
-            (GridElement::Free, _) => Ok(()),
-            (GridElement::Robot, _) => panic!("Multiple robots in grid!"),
-            (GridElement::BoxL | GridElement::BoxR, Move::Up | Move::Down) => {
-                let offset: isize = if next_element == GridElement::BoxL {
-                    1
-                } else {
-                    -1
-                };
-
-                let r1 = self.apply_move_inner((nx, ny), mv);
-                let r2 = self.apply_move_inner(((nx as isize + offset) as usize, ny), mv);
-
-                match (r1, r2) {
-                    (Ok(_), Ok(_)) => Ok(()),
-                    _ => Err(MyError),
+        self.get(pos) == self.get(neighbor)
+    }
+
+    fn element_char(&self, pos: (usize, usize)) -> char {
+        match self.elements[pos.1][pos.0] {
+            GridElement::Wall => '#',
+            GridElement::Free => '.',
+            GridElement::Robot => '@',
+            GridElement::Box(_) if self.same_box(pos, Move::Up) => '|',
+            GridElement::Box(_) => {
+                match (
+                    self.same_box(pos, Move::Left),
+                    self.same_box(pos, Move::Right),
+                ) {
+                    (false, false) => 'O',
+                    (false, true) => '[',
+                    (true, true) => '=',
+                    (true, false) => ']',
                 }
             }
-            (_, _) => self.apply_move_inner((nx, ny), mv),
-        };
-
-        // Element at current location might not actually end up free. But if this is the case it
-        // will be overwritten with the correct value after this function returns.
-        self.elements[y][x] = GridElement::Free;
-        self.elements[ny][nx] = current_element;
+        }
+    }
 
-        if current_element == GridElement::Robot {
-            self.robot_loc = (nx, ny);
+    /// All of the cells covered by the box at `pos`.
+    fn box_cells(&self, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut cells = vec![pos];
+        let mut seen: HashSet<(usize, usize)> = HashSet::from([pos]);
+        let mut i = 0;
+
+        while i < cells.len() {
+            let cell = cells[i];
+            for mv in [Move::Up, Move::Down, Move::Left, Move::Right] {
+                if self.same_box(cell, mv) && seen.insert(mv.step(cell)) {
+                    cells.push(mv.step(cell));
+                }
+            }
+            i += 1;
         }
 
-        res
+        cells
     }
 
-    fn apply_move(&mut self, mv: Move) {
-        let mut new_self = self.clone();
+    /// Push the robot one step, along with any boxes in the way.
+    ///
+    /// This works in two phases. First every cell that would have to move is collected, following
+    /// chains of boxes and including every cell of a box as soon as any part of it is pushed. If
+    /// any of them would hit a wall nothing moves. Otherwise all of the collected cells are
+    /// shifted at once. Returns the cells which changed, which is empty if the move was blocked.
+    fn apply_move(&mut self, mv: Move) -> Diff {
+        let robot_before = self.robot_loc;
+
+        let mut to_move: Vec<(usize, usize)> = vec![self.robot_loc];
+        let mut pushed: HashSet<usize> = HashSet::new();
+        let mut frontier = vec![self.robot_loc];
+
+        while let Some(pos) = frontier.pop() {
+            let (nx, ny) = mv.step(pos);
+            match self.elements[ny][nx] {
+                GridElement::Wall => {
+                    return Diff {
+                        robot_before,
+                        robot_after: robot_before,
+                        changes: Vec::new(),
+                    }
+                }
+                GridElement::Free => {}
+                GridElement::Robot => panic!("Multiple robots in grid!"),
+                GridElement::Box(id) => {
+                    if pushed.insert(id) {
+                        let cells = self.box_cells((nx, ny));
+                        to_move.extend_from_slice(&cells);
+                        frontier.extend_from_slice(&cells);
+                    }
+                }
+            }
+        }
+
+        // Every cell we leave becomes free unless something else moves into it
+        let mut changes: HashMap<(usize, usize), Change> = HashMap::new();
+        for pos in to_move.iter() {
+            changes.insert(
+                *pos,
+                Change {
+                    pos: *pos,
+                    before: self.elements[pos.1][pos.0],
+                    after: GridElement::Free,
+                },
+            );
+        }
 
-        if new_self.apply_move_inner(self.robot_loc, mv).is_ok() {
-            self.elements = new_self.elements;
-            self.robot_loc = new_self.robot_loc;
+        for pos in to_move.iter() {
+            let ge = self.elements[pos.1][pos.0];
+            let next = mv.step(*pos);
+            changes
+                .entry(next)
+                .or_insert(Change {
+                    pos: next,
+                    before: self.elements[next.1][next.0],
+                    after: GridElement::Free,
+                })
+                .after = ge;
         }
+
+        let mut changes: Vec<Change> = changes
+            .into_values()
+            .filter(|change| change.before != change.after)
+            .collect();
+        changes.sort_by_key(|change| (change.pos.1, change.pos.0));
+
+        let diff = Diff {
+            robot_before,
+            robot_after: mv.step(robot_before),
+            changes,
+        };
+
+        self.apply_diff(&diff);
+
+        diff
     }
 
+    /// Sum of the GPS coordinates of every box, measured at its top left corner.
     fn gps_score(&self) -> u64 {
         self.elements
             .iter()
             .enumerate()
-            .map(|(y, line)| {
+            .flat_map(|(y, line)| {
                 line.iter().enumerate().filter_map(move |(x, ge)| {
-                    if let GridElement::Box | GridElement::BoxL = ge {
-                        Some((100 * y + x) as u64)
-                    } else {
-                        None
+                    if let GridElement::Box(_) = ge {
+                        if !self.same_box((x, y), Move::Left) && !self.same_box((x, y), Move::Up) {
+                            return Some((100 * y + x) as u64);
+                        }
                     }
+                    None
                 })
             })
-            .flatten()
             .sum()
     }
 
-    fn expanded(&self) -> Self {
+    /// Scale the whole warehouse up, so that every cell becomes a `width` by `height` block.
+    ///
+    /// Walls and boxes grow to fill their block, while the robot stays the same size in the top
+    /// left corner. Scaling an already scaled grid is fine, and just multiplies the factors.
+    fn scaled(&self, width: usize, height: usize) -> Self {
         let new_elements: Vec<Vec<GridElement>> = self
             .elements
             .iter()
-            .map(|line| {
-                line.iter()
-                    .flat_map(|ge| match ge {
-                        GridElement::Wall => vec![GridElement::Wall, GridElement::Wall],
-                        GridElement::Free => vec![GridElement::Free, GridElement::Free],
-                        GridElement::Robot => vec![GridElement::Robot, GridElement::Free],
-                        GridElement::Box => vec![GridElement::BoxL, GridElement::BoxR],
-                        _ => panic!("Cannot expand an already expanded grid"),
-                    })
-                    .collect()
+            .flat_map(|line| {
+                (0..height).map(move |dy| {
+                    line.iter()
+                        .flat_map(|ge| {
+                            (0..width).map(move |dx| match ge {
+                                GridElement::Robot if dx > 0 || dy > 0 => GridElement::Free,
+                                _ => *ge,
+                            })
+                        })
+                        .collect()
+                })
             })
             .collect();
 
         Self {
             elements: new_elements,
-            robot_loc: (self.robot_loc.0 * 2, self.robot_loc.1),
+            robot_loc: (self.robot_loc.0 * width, self.robot_loc.1 * height),
         }
     }
+
+    fn expanded(&self) -> Self {
+        self.scaled(2, 1)
+    }
 }
 
-fn run(input: &str) -> (u64, u64) {
+/// A single grid cell which was changed by a move.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Change {
+    pos: (usize, usize),
+    before: GridElement,
+    after: GridElement,
+}
+
+/// Everything needed to apply or undo a single move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Diff {
+    robot_before: (usize, usize),
+    robot_after: (usize, usize),
+    changes: Vec<Change>,
+}
+
+impl Grid {
+    fn apply_diff(&mut self, diff: &Diff) {
+        for change in diff.changes.iter() {
+            self.elements[change.pos.1][change.pos.0] = change.after;
+        }
+        self.robot_loc = diff.robot_after;
+    }
+
+    fn undo_diff(&mut self, diff: &Diff) {
+        for change in diff.changes.iter() {
+            self.elements[change.pos.1][change.pos.0] = change.before;
+        }
+        self.robot_loc = diff.robot_before;
+    }
+}
+
+/// Steps through a list of moves one at a time, recording what each move changed so that it can
+/// be stepped backwards again.
+#[derive(Debug, Clone)]
+struct Replay {
+    grid: Grid,
+    moves: Vec<Move>,
+    /// The diff for each move which has been applied at least once.
+    diffs: Vec<Diff>,
+    /// Number of moves currently applied to `grid`.
+    step: usize,
+}
+
+impl Replay {
+    fn new(grid: Grid, moves: Vec<Move>) -> Self {
+        Self {
+            grid,
+            moves,
+            diffs: Vec::new(),
+            step: 0,
+        }
+    }
+
+    /// Apply the next move. Returns false if there are no moves left.
+    fn step_forward(&mut self) -> bool {
+        if self.step >= self.moves.len() {
+            return false;
+        }
+
+        if self.step < self.diffs.len() {
+            self.grid.apply_diff(&self.diffs[self.step]);
+        } else {
+            let diff = self.grid.apply_move(self.moves[self.step]);
+            self.diffs.push(diff);
+        }
+
+        self.step += 1;
+        true
+    }
+
+    /// Undo the last move. Returns false if we're already back at the start.
+    fn step_backward(&mut self) -> bool {
+        if self.step == 0 {
+            return false;
+        }
+
+        self.step -= 1;
+        self.grid.undo_diff(&self.diffs[self.step]);
+        true
+    }
+
+    /// Move the replay to a specific step, clamped to the number of moves.
+    fn seek(&mut self, step: usize) {
+        while self.step < step && self.step_forward() {}
+        while self.step > step && self.step_backward() {}
+    }
+
+    /// Rewind to the start and render the grid after every step, including the starting grid.
+    fn frames(&mut self) -> Vec<String> {
+        self.seek(0);
+
+        let mut out = vec![self.grid.to_string()];
+        while self.step_forward() {
+            out.push(self.grid.to_string());
+        }
+
+        out
+    }
+
+    /// Write every frame to `dir` as a text file, named by step number, e.g. `step_00042.txt`.
+    fn write_frames(&mut self, dir: impl AsRef<Path>) -> io::Result<()> {
+        fs::create_dir_all(&dir)?;
+
+        for (step, frame) in self.frames().iter().enumerate() {
+            let path = dir.as_ref().join(format!("step_{:05}.txt", step));
+            fs::write(path, frame)?;
+        }
+
+        Ok(())
+    }
+
+    /// Play every frame in the terminal, clearing the screen in between.
+    fn animate(&mut self, delay: Duration) {
+        for (step, frame) in self.frames().iter().enumerate() {
+            let mv = step
+                .checked_sub(1)
+                .map(|i| format!("{:?}", self.moves[i]))
+                .unwrap_or_default();
+            println!(
+                "\x1b[2J\x1b[H{}step {}/{} {}",
+                frame,
+                step,
+                self.moves.len(),
+                mv
+            );
+            thread::sleep(delay);
+        }
+    }
+}
+
+fn parse(input: &str) -> (Grid, Vec<Move>) {
     let (grid, moves) = input.split_once("\n\n").unwrap();
 
     let grid = Grid::from(grid);
@@ -230,6 +471,20 @@ fn run(input: &str) -> (u64, u64) {
         .filter_map(|ch| Move::try_from(ch).ok())
         .collect();
 
+    (grid, moves)
+}
+
+/// Write the frames of both parts to the `pt1` and `pt2` subdirectories of `dir`.
+fn write_replays(input: &str, dir: &Path) -> io::Result<()> {
+    let (grid, moves) = parse(input);
+
+    Replay::new(grid.expanded(), moves.clone()).write_frames(dir.join("pt2"))?;
+    Replay::new(grid, moves).write_frames(dir.join("pt1"))
+}
+
+fn run(input: &str) -> (u64, u64) {
+    let (grid, moves) = parse(input);
+
     let mut g = grid.clone();
     for m in moves.iter() {
         g.apply_move(*m);
@@ -265,4 +520,214 @@ mod test {
         let (pt1, _pt2) = run(&input);
         assert_eq!(pt1, 2028);
     }
+
+    #[test]
+    fn test_replay() {
+        let input = include_str!("../inputs/15_2.ex");
+        let (grid, moves) = parse(input);
+        let mut replay = Replay::new(grid.clone(), moves.clone());
+
+        let mut expected = grid.clone();
+        let mut states = vec![expected.to_string()];
+        for m in moves.iter() {
+            expected.apply_move(*m);
+            states.push(expected.to_string());
+        }
+
+        // Step all the way forward, then all the way back again
+        for state in states.iter().skip(1) {
+            assert!(replay.step_forward());
+            assert_eq!(&replay.grid.to_string(), state);
+        }
+        assert!(!replay.step_forward());
+        assert_eq!(replay.grid.gps_score(), 2028);
+
+        for state in states.iter().rev().skip(1) {
+            assert!(replay.step_backward());
+            assert_eq!(&replay.grid.to_string(), state);
+        }
+        assert!(!replay.step_backward());
+        assert_eq!(replay.grid.robot_loc, grid.robot_loc);
+
+        replay.seek(5);
+        assert_eq!(replay.grid.to_string(), states[5]);
+        assert_eq!(replay.frames(), states);
+    }
+
+    #[test]
+    fn test_replay_expanded() {
+        let input = include_str!("../inputs/15.ex");
+        let (grid, moves) = parse(input);
+        let mut replay = Replay::new(grid.expanded(), moves);
+
+        replay.seek(usize::MAX);
+        assert_eq!(replay.grid.gps_score(), 9021);
+
+        replay.seek(0);
+        assert_eq!(replay.grid.to_string(), grid.expanded().to_string());
+    }
+
+    #[test]
+    fn test_write_replays() {
+        let input = include_str!("../inputs/15_2.ex");
+        let (grid, moves) = parse(input);
+        let dir = std::env::temp_dir().join("aoc2024_day15_frames");
+        let _ = fs::remove_dir_all(&dir);
+
+        write_replays(input, &dir).unwrap();
+
+        // One frame for the start, plus one per move, in each part
+        for (part, grid) in [("pt1", grid.clone()), ("pt2", grid.expanded())] {
+            let frames = Replay::new(grid, moves.clone()).frames();
+            assert_eq!(
+                fs::read_dir(dir.join(part)).unwrap().count(),
+                moves.len() + 1
+            );
+            for (step, frame) in frames.iter().enumerate() {
+                let path = dir.join(part).join(format!("step_{:05}.txt", step));
+                assert_eq!(&fs::read_to_string(path).unwrap(), frame);
+            }
+        }
+        let last = fs::read_to_string(dir.join("pt1/step_00015.txt")).unwrap();
+        assert_eq!(Grid::from(last.as_str()).gps_score(), 2028);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_diff() {
+        let input = include_str!("../inputs/15_2.ex");
+        let (grid, _moves) = parse(input);
+
+        let mut after = grid.clone();
+        let diff = after.apply_move(Move::Right);
+        assert_eq!(diff.robot_before, (2, 2));
+        assert_eq!(diff.robot_after, (3, 2));
+        assert_eq!(
+            diff.changes,
+            vec![
+                Change {
+                    pos: (2, 2),
+                    before: GridElement::Robot,
+                    after: GridElement::Free
+                },
+                Change {
+                    pos: (3, 2),
+                    before: GridElement::Free,
+                    after: GridElement::Robot
+                },
+            ]
+        );
+
+        // Moving into a wall doesn't change anything
+        let mut after = grid.clone();
+        let diff = after.apply_move(Move::Left);
+        assert!(diff.changes.is_empty());
+        assert_eq!(diff.robot_after, (2, 2));
+    }
+
+    #[test]
+    fn test_push_wide_boxes() {
+        // The right half of the box is free to move up, but the left half is blocked
+        let mut grid = Grid::from("######\n#.#..#\n#.[].#\n#..@.#\n######");
+        let before = grid.to_string();
+        assert!(grid.apply_move(Move::Up).changes.is_empty());
+        assert_eq!(grid.to_string(), before);
+
+        // Two boxes stacked offset from each other move together
+        let mut grid = Grid::from("#######\n#.....#\n#.[]..#\n#..[].#\n#...@.#\n#######");
+        let diff = grid.apply_move(Move::Up);
+        assert_eq!(diff.changes.len(), 8);
+        assert_eq!(
+            grid.to_string(),
+            "#######\n#.[]..#\n#..[].#\n#...@.#\n#.....#\n#######\n"
+        );
+        assert_eq!(grid.robot_loc, (4, 3));
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let text = "########\n#.O[=]@#\n#[].[]O#\n########\n";
+        let grid = Grid::from(text);
+        assert_eq!(grid.to_string(), text);
+        assert_eq!(grid.robot_loc, (6, 1));
+        assert_eq!(grid.gps_score(), 102 + 103 + 201 + 204 + 206);
+
+        // Tall boxes round trip, and stay separate from boxes stacked on top of them
+        let tall = Grid::from("#######\n#O[].@#\n#|||O.#\n#O[]|.#\n#######").scaled(1, 2);
+        assert_eq!(tall.gps_score(), 201 + 202 + 404 + 601 + 602);
+        let text = tall.to_string();
+        assert_eq!(
+            text,
+            "#######\n#######\n#O[].@#\n#|||..#\n#|||O.#\n#||||.#\n#O[]|.#\n#||||.#\n#######\n#######\n"
+        );
+        let parsed = Grid::from(text.as_str());
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(parsed.gps_score(), tall.gps_score());
+    }
+
+    #[test]
+    fn test_scaled() {
+        let input = include_str!("../inputs/15.ex");
+        let (grid, moves) = parse(input);
+
+        // Scaling by 1 doesn't change anything
+        let mut g = grid.scaled(1, 1);
+        assert_eq!(g.to_string(), grid.to_string());
+        for m in moves.iter() {
+            g.apply_move(*m);
+        }
+        assert_eq!(g.gps_score(), 10092);
+
+        // Scaling an expanded grid is the same as expanding by a larger factor
+        let grid = Grid::from("#####\n#O@.#\n#####");
+        assert_eq!(
+            grid.expanded().to_string(),
+            "##########\n##[]@...##\n##########\n"
+        );
+        assert_eq!(
+            grid.expanded().scaled(2, 1).to_string(),
+            grid.scaled(4, 1).to_string()
+        );
+        assert_eq!(
+            grid.scaled(3, 2).to_string(),
+            "###############\n###############\n###[=]@.....###\n###|||......###\n###############\n###############\n"
+        );
+    }
+
+    #[test]
+    fn test_push_wide_and_tall_boxes() {
+        // A 3 wide box is pushed by its middle
+        let mut grid = Grid::from("#######\n#.....#\n#.[=].#\n#..@..#\n#######");
+        grid.apply_move(Move::Up);
+        assert_eq!(
+            grid.to_string(),
+            "#######\n#.[=].#\n#..@..#\n#.....#\n#######\n"
+        );
+        assert_eq!(grid.gps_score(), 102);
+
+        // But it can't go any further, even though only one end is blocked
+        let mut grid = Grid::from("#######\n#.#...#\n#.[=].#\n#..@..#\n#######");
+        assert!(grid.apply_move(Move::Up).changes.is_empty());
+
+        // Boxes 2 tall move as a single unit, and are scored by their top left corner
+        let grid = Grid::from("######\n#....#\n#.O@.#\n#....#\n######");
+        let mut tall = grid.scaled(1, 2);
+        assert_eq!(tall.gps_score(), 402);
+        tall.apply_move(Move::Down);
+        tall.apply_move(Move::Left);
+        tall.apply_move(Move::Left);
+        assert_eq!(tall.gps_score(), 401);
+
+        // Pushing from above moves the whole box, not just the top cell
+        tall.apply_move(Move::Up);
+        tall.apply_move(Move::Up);
+        tall.apply_move(Move::Left);
+        tall.apply_move(Move::Down);
+        assert_eq!(tall.gps_score(), 501);
+        assert_eq!(
+            tall.to_string(),
+            "######\n######\n#....#\n#....#\n#@...#\n#O...#\n#|...#\n#....#\n######\n######\n"
+        );
+    }
 }
@@ -2,24 +2,74 @@ use priority_queue::PriorityQueue;
 use std::{
     cmp::Reverse,
     collections::{HashMap, HashSet},
+    fmt::Write,
     time::Instant,
 };
 
+/// Cost of moving forward one tile in the puzzle
+pub const MOVE_COST: usize = 1;
+/// Cost of turning 90 degrees in the puzzle
+pub const TURN_COST: usize = 1000;
+
 pub fn run_outer() -> String {
+    run_outer_with(MOVE_COST, TURN_COST, false, None)
+}
+
+/// Run with different costs for moving and turning to the puzzle, and optionally draw the maze
+/// with every tile on a best path marked, or each distinct best path separately up to `path_limit`
+/// of them.
+pub fn run_outer_with(
+    step: usize,
+    turn: usize,
+    show_tiles: bool,
+    path_limit: Option<usize>,
+) -> String {
     let input = include_str!("../inputs/16.in");
+    let costs = Costs { step, turn };
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let maze = Maze::from(input);
+    let solution = solve(&maze, costs, Search::AStar);
+    let (pt1, pt2) = answers(&solution);
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
         pt1,
         pt2,
         elapsed.as_micros()
-    )
+    );
+
+    if show_tiles {
+        write!(out, "\n{}", render_best_tiles(&maze, &solution).trim_end()).unwrap();
+    }
+
+    if let Some(limit) = path_limit {
+        for (i, path) in render_best_paths(&maze, &solution, limit)
+            .iter()
+            .enumerate()
+        {
+            write!(out, "\npath {}:\n{}", i + 1, path.trim_end()).unwrap();
+        }
+    }
+
+    out
 }
 
-const MOVE_COST: usize = 1;
-const TURN_COST: usize = 1000;
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Costs {
+    /// Cost of moving forward one tile
+    step: usize,
+    /// Cost of turning 90 degrees in either direction
+    turn: usize,
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Self {
+            step: MOVE_COST,
+            turn: TURN_COST,
+        }
+    }
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 enum Dir {
@@ -41,8 +91,6 @@ impl Dir {
     }
 }
 
-const ALL_DIRECTIONS: [Dir; 4] = [Dir::North, Dir::East, Dir::South, Dir::West];
-
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 struct Node {
     dir: Dir,
@@ -52,6 +100,7 @@ struct Node {
 
 struct AdjacentNodeIter {
     start: Node,
+    costs: Costs,
     count: usize,
 }
 
@@ -66,28 +115,28 @@ impl Iterator for AdjacentNodeIter {
                         y: self.start.y - 1,
                         ..self.start
                     },
-                    MOVE_COST,
+                    self.costs.step,
                 ),
                 Dir::East => (
                     Node {
                         x: self.start.x + 1,
                         ..self.start
                     },
-                    MOVE_COST,
+                    self.costs.step,
                 ),
                 Dir::South => (
                     Node {
                         y: self.start.y + 1,
                         ..self.start
                     },
-                    MOVE_COST,
+                    self.costs.step,
                 ),
                 Dir::West => (
                     Node {
                         x: self.start.x - 1,
                         ..self.start
                     },
-                    MOVE_COST,
+                    self.costs.step,
                 ),
             },
             1 => (
@@ -95,21 +144,21 @@ impl Iterator for AdjacentNodeIter {
                     dir: self.start.dir.turn_cw(),
                     ..self.start
                 },
-                TURN_COST,
+                self.costs.turn,
             ),
             2 => (
                 Node {
                     dir: self.start.dir.turn_cw().turn_cw(),
                     ..self.start
                 },
-                2 * TURN_COST,
+                2 * self.costs.turn,
             ),
             3 => (
                 Node {
                     dir: self.start.dir.turn_cw().turn_cw().turn_cw(),
                     ..self.start
                 },
-                TURN_COST,
+                self.costs.turn,
             ),
             _ => return None,
         };
@@ -120,143 +169,345 @@ impl Iterator for AdjacentNodeIter {
 }
 
 impl Node {
-    fn adjacent(&self) -> AdjacentNodeIter {
+    fn adjacent(&self, costs: Costs) -> AdjacentNodeIter {
         AdjacentNodeIter {
-            start: self.clone(),
+            start: *self,
+            costs,
             count: 0,
         }
     }
 }
 
-fn run(input: &str) -> (u64, u64) {
-    let mut start_node = Node::default();
-    let mut end = (0, 0);
-    let mut nodes: Vec<Node> = Vec::new();
-
-    for (y, line) in input.trim().lines().enumerate() {
-        for (x, ch) in line.chars().enumerate() {
-            let should_insert = match ch {
-                '.' => true,
-                'S' => {
-                    start_node = Node {
-                        dir: Dir::East,
-                        x: x as isize,
-                        y: y as isize,
-                    };
-                    true
-                }
-                'E' => {
-                    end = (x as isize, y as isize);
-                    true
-                }
-                _ => false,
-            };
+struct Maze {
+    dimensions: (isize, isize),
+    open: HashSet<(isize, isize)>,
+    start_node: Node,
+    end: (isize, isize),
+}
 
-            if should_insert {
-                for dir in ALL_DIRECTIONS.iter() {
-                    nodes.push(Node {
-                        dir: *dir,
-                        x: x as isize,
-                        y: y as isize,
-                    });
+impl From<&str> for Maze {
+    fn from(s: &str) -> Self {
+        let mut start_node = Node::default();
+        let mut end = (0, 0);
+        let mut open = HashSet::new();
+        let mut dimensions = (0, 0);
+
+        for (y, line) in s.trim().lines().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                let should_insert = match ch {
+                    '.' => true,
+                    'S' => {
+                        start_node = Node {
+                            dir: Dir::East,
+                            x: x as isize,
+                            y: y as isize,
+                        };
+                        true
+                    }
+                    'E' => {
+                        end = (x as isize, y as isize);
+                        true
+                    }
+                    _ => false,
+                };
+
+                if should_insert {
+                    open.insert((x as isize, y as isize));
                 }
             }
+
+            dimensions = (line.len() as isize, y as isize + 1);
+        }
+
+        Maze {
+            dimensions,
+            open,
+            start_node,
+            end,
         }
     }
+}
 
-    let mut unvisited: PriorityQueue<Node, Reverse<usize>> = nodes
-        .iter()
-        .map(|n| {
-            if *n == start_node {
-                (*n, Reverse(0))
+impl Maze {
+    /// Number of distinct nodes (tile and facing direction) in the maze.
+    fn node_count(&self) -> usize {
+        (self.dimensions.0 * self.dimensions.1) as usize * 4
+    }
+
+    /// Dense index of a node, for per-node state stored in a `Vec`.
+    fn index(&self, node: &Node) -> usize {
+        (node.y * self.dimensions.0 + node.x) as usize * 4 + node.dir as usize
+    }
+}
+
+/// The result of searching the maze for the cheapest route from start to end.
+struct Solution {
+    start_node: Node,
+    /// Lowest cost of reaching the end tile
+    cost: usize,
+    /// Every orientation at the end tile which can be reached at the lowest cost
+    end_nodes: Vec<Node>,
+    /// For each node, all of the nodes it can be reached from at its lowest cost
+    prev: HashMap<Node, Vec<Node>>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Search {
+    /// Plain Dijkstra, exploring outwards from the start in order of cost
+    #[allow(unused)]
+    Dijkstra,
+    /// A*, directing the search towards the end tile using `heuristic`
+    AStar,
+}
+
+/// Lower bound on the cost from `node` to the end tile: the Manhattan distance, plus the fewest
+/// turns needed to face every direction the end lies in. Neither term can drop by more than the
+/// cost of a single move or turn, so this is consistent and A* still settles nodes in order.
+fn heuristic(node: &Node, end: (isize, isize), costs: Costs) -> usize {
+    let dx = end.0 - node.x;
+    let dy = end.1 - node.y;
+
+    let horizontal = match dx.signum() {
+        1 => Some(Dir::East),
+        -1 => Some(Dir::West),
+        _ => None,
+    };
+    let vertical = match dy.signum() {
+        1 => Some(Dir::South),
+        -1 => Some(Dir::North),
+        _ => None,
+    };
+
+    let turns = match (horizontal, vertical) {
+        (None, None) => 0,
+        (Some(d), None) | (None, Some(d)) => {
+            if node.dir == d {
+                0
+            } else if node.dir == d.turn_cw().turn_cw() {
+                2
             } else {
-                (*n, Reverse(usize::MAX))
+                1
             }
-        })
-        .collect();
-    let mut visited: Vec<(Node, usize)> = Vec::new();
+        }
+        (Some(h), Some(v)) => {
+            if node.dir == h || node.dir == v {
+                1
+            } else {
+                2
+            }
+        }
+    };
+
+    (dx.unsigned_abs() + dy.unsigned_abs()) * costs.step + turns * costs.turn
+}
+
+fn solve(maze: &Maze, costs: Costs, search: Search) -> Solution {
+    let start_node = maze.start_node;
+    let estimate = |node: &Node| match search {
+        Search::Dijkstra => 0,
+        Search::AStar => heuristic(node, maze.end, costs),
+    };
+
+    // Nodes are only added to the queue once they are reached, prioritised by their cost so far
+    // plus the estimate of the remaining cost
+    let mut queue: PriorityQueue<Node, Reverse<usize>> = PriorityQueue::new();
+    queue.push(start_node, Reverse(estimate(&start_node)));
+    let mut dist: Vec<usize> = vec![usize::MAX; maze.node_count()];
+    dist[maze.index(&start_node)] = 0;
+    let mut settled: Vec<bool> = vec![false; maze.node_count()];
     let mut prev: HashMap<Node, Vec<Node>> = HashMap::new();
+    let mut best: Option<usize> = None;
+    let mut end_nodes: Vec<Node> = Vec::new();
 
-    while let Some((current, Reverse(cost))) = unvisited.pop() {
-        // End condition of loop: we might not be able to visit all nodes, and need to break if the
-        // node we pop has an infinite cost
-        if cost == usize::MAX {
+    while let Some((current, Reverse(priority))) = queue.pop() {
+        // Once the queue moves past the best cost to the end, no remaining node can be on a best
+        // path. Nodes tied with the best cost are still processed so that part 2 sees all of them.
+        if best.is_some_and(|best| priority > best) {
             break;
         }
 
-        for (neighbor, additional_cost) in current.adjacent() {
+        let cost = dist[maze.index(&current)];
+        settled[maze.index(&current)] = true;
+
+        if (current.x, current.y) == maze.end {
+            best = Some(cost);
+            end_nodes.push(current);
+            continue;
+        }
+
+        for (neighbor, additional_cost) in current.adjacent(costs) {
+            // Turning never leaves the current tile, so only moves need checking for walls
+            let moved = (neighbor.x, neighbor.y) != (current.x, current.y);
+            if moved && !maze.open.contains(&(neighbor.x, neighbor.y)) {
+                continue;
+            }
+
+            let idx = maze.index(&neighbor);
             let new_cost = cost + additional_cost;
-            if let Some(Reverse(existing_cost)) = unvisited.get_priority(&neighbor) {
-                if new_cost < *existing_cost {
-                    // Update the cost of this node
-                    unvisited.change_priority(&neighbor, Reverse(new_cost));
-
-                    // Replace the existing previous node(s) of the unvisited node with the
-                    // current node
-                    prev.insert(neighbor, vec![current]);
-                } else if new_cost == *existing_cost {
-                    // If the cost is equal, append to the array of previous nodes (since there
-                    // is now more than one lowest-cost previous node)
-                    let p = prev
-                        .get_mut(&neighbor)
-                        .expect("Neighbor should already have an entry in prev");
-                    p.push(current);
-                }
+            if new_cost == dist[idx] {
+                // If the cost is equal, append to the array of previous nodes (since there is now
+                // more than one lowest-cost previous node). With a consistent heuristic this can
+                // happen after the neighbor has been settled. Free edges mean this can also link
+                // nodes into a loop, which anything walking back through `prev` has to allow for.
+                prev.entry(neighbor).or_default().push(current);
+            } else if new_cost < dist[idx] && !settled[idx] {
+                // Replace the existing previous node(s) of the neighbor with the current node, and
+                // (re)queue it with its new cost
+                dist[idx] = new_cost;
+                prev.insert(neighbor, vec![current]);
+                queue.push(neighbor, Reverse(new_cost + estimate(&neighbor)));
             }
         }
+    }
 
-        visited.push((current, cost));
+    Solution {
+        start_node,
+        cost: best.expect("End should be reachable from the start"),
+        end_nodes,
+        prev,
     }
+}
 
-    // Find the end node in the visited array, and check the min cost. Note that we will actually
-    // have four end nodes (one for each direction we can face in), any of these is an acceptable
-    // end point so check all of them for the min cost.
-    let mut pt1 = usize::MAX;
-    let mut end_node: Option<Node> = None;
-    for (node, cost) in visited.iter() {
-        if (node.x, node.y) == end {
-            if *cost < pt1 {
-                end_node = Some(*node);
-                pt1 = *cost;
+impl Solution {
+    /// All tiles which are on at least one best path.
+    fn best_path_tiles(&self) -> HashSet<(isize, isize)> {
+        // Traverse the map of previous nodes, starting from the end nodes until we get to the
+        // start node. We need to count up the nodes with unique locations (the same location but
+        // different direction does not count as an additional node on the best path)
+        let mut pending_nodes: Vec<Node> = self.end_nodes.clone();
+        let mut seen: HashSet<Node> = pending_nodes.iter().copied().collect();
+        let mut best_path: HashSet<(isize, isize)> =
+            pending_nodes.iter().map(|node| (node.x, node.y)).collect();
+
+        while let Some(next) = pending_nodes.pop() {
+            if next == self.start_node {
+                continue;
+            }
+
+            let new_nodes = self
+                .prev
+                .get(&next)
+                .expect("Any node in the pending list should have a previous node");
+            for node in new_nodes {
+                best_path.insert((node.x, node.y));
+                if seen.insert(*node) {
+                    pending_nodes.push(*node);
+                }
             }
         }
+
+        best_path
     }
 
-    // Traverse the map of previous nodes, starting from the end node until we get to the start
-    // node. We need to count up the nodes with unique locations (the same location but different
-    // direction does not count as an additional node on the best path)
-    let end_node = end_node.unwrap();
-    let mut pending_nodes: Vec<Node> = vec![end_node];
-    let mut best_path: HashSet<(isize, isize)> = HashSet::new();
-    best_path.insert((end_node.x, end_node.y));
-
-    while let Some(next) = pending_nodes.pop() {
-        let new_nodes = prev
-            .get(&next)
-            .expect("Any node in the pending list should have a previous node");
-        for node in new_nodes {
-            best_path.insert((node.x, node.y));
-            if *node != start_node {
-                pending_nodes.push(*node);
+    /// Enumerate up to `limit` distinct best paths, each as the list of nodes from start to end.
+    /// Turning on the spot shows up as consecutive nodes on the same tile.
+    fn best_paths(&self, limit: usize) -> Vec<Vec<Node>> {
+        let mut paths = Vec::new();
+        let mut stack: Vec<Vec<Node>> = self.end_nodes.iter().map(|node| vec![*node]).collect();
+
+        while let Some(path) = stack.pop() {
+            if paths.len() >= limit {
+                break;
+            }
+
+            let last = *path.last().unwrap();
+            if last == self.start_node {
+                paths.push(path.into_iter().rev().collect());
+                continue;
+            }
+
+            // The turns already made on this tile, later on in the path
+            let turns: Vec<&Node> = path
+                .iter()
+                .rev()
+                .skip(1)
+                .take_while(|n| (n.x, n.y) == (last.x, last.y))
+                .collect();
+
+            for node in self.prev[&last].iter().rev() {
+                // Never repeat a node, and when turning is free don't turn the long way round to a
+                // direction which one of the later turns could have started from directly
+                let redundant_turn = (node.x, node.y) == (last.x, last.y)
+                    && turns.iter().any(|n| self.prev[n].contains(node));
+                if redundant_turn || path.contains(node) {
+                    continue;
+                }
+
+                let mut next_path = path.clone();
+                next_path.push(*node);
+                stack.push(next_path);
             }
         }
+
+        paths
+    }
+}
+
+/// Render the maze with every tile in `tiles` marked with `O`.
+fn render(maze: &Maze, tiles: &HashSet<(isize, isize)>) -> String {
+    let mut out = String::new();
+
+    for y in 0..maze.dimensions.1 {
+        for x in 0..maze.dimensions.0 {
+            let ch = if (x, y) == (maze.start_node.x, maze.start_node.y) {
+                'S'
+            } else if (x, y) == maze.end {
+                'E'
+            } else if tiles.contains(&(x, y)) {
+                'O'
+            } else if maze.open.contains(&(x, y)) {
+                '.'
+            } else {
+                '#'
+            };
+            out.push(ch);
+        }
+        out.push('\n');
     }
 
-    let pt2 = best_path.len() as u64;
+    out
+}
+
+/// Render the maze with every tile on any best path marked.
+fn render_best_tiles(maze: &Maze, solution: &Solution) -> String {
+    render(maze, &solution.best_path_tiles())
+}
+
+/// Render each distinct best path separately, up to `limit` of them.
+fn render_best_paths(maze: &Maze, solution: &Solution, limit: usize) -> Vec<String> {
+    solution
+        .best_paths(limit)
+        .iter()
+        .map(|path| {
+            let tiles: HashSet<(isize, isize)> = path.iter().map(|n| (n.x, n.y)).collect();
+            render(maze, &tiles)
+        })
+        .collect()
+}
+
+/// The best score, and the number of tiles on any best path.
+fn answers(solution: &Solution) -> (u64, u64) {
+    let pt1 = solution.cost as u64;
+    let pt2 = solution.best_path_tiles().len() as u64;
 
-    (pt1 as u64, pt2)
+    (pt1, pt2)
 }
 
 #[cfg(test)]
 mod test {
-    use itertools::assert_equal;
+    use itertools::{assert_equal, Itertools};
 
     use super::*;
 
+    fn run_inner(input: &str, costs: Costs) -> (u64, u64) {
+        let maze = Maze::from(input);
+        answers(&solve(&maze, costs, Search::AStar))
+    }
+
     #[test]
     fn test_example() {
         let input = include_str!("../inputs/16.ex");
-        let (pt1, pt2) = run(&input);
+        let (pt1, pt2) = run_inner(input, Costs::default());
         assert_eq!(pt1, 7036);
         assert_eq!(pt2, 45);
     }
@@ -264,7 +515,7 @@ mod test {
     #[test]
     fn test_example_2() {
         let input = include_str!("../inputs/16_2.ex");
-        let (pt1, pt2) = run(&input);
+        let (pt1, pt2) = run_inner(input, Costs::default());
         assert_eq!(pt1, 11048);
         assert_eq!(pt2, 64);
     }
@@ -311,11 +562,147 @@ mod test {
             ),
         ];
 
-        let mut actual: Vec<(Node, usize)> = node.adjacent().collect();
+        let mut actual: Vec<(Node, usize)> = node.adjacent(Costs::default()).collect();
 
         expected.sort();
         actual.sort();
 
         assert_equal(actual, expected);
     }
+
+    #[test]
+    fn test_costs() {
+        let input = include_str!("../inputs/16.ex");
+
+        // With no turning cost, the best paths are simply the shortest ones
+        let costs = Costs { step: 1, turn: 0 };
+        let (pt1, _pt2) = run_inner(input, costs);
+        assert_eq!(pt1, 28);
+
+        // Scaling both costs scales the score but not the best paths
+        let costs = Costs {
+            step: 2,
+            turn: 2000,
+        };
+        let (pt1, pt2) = run_inner(input, costs);
+        assert_eq!(pt1, 2 * 7036);
+        assert_eq!(pt2, 45);
+    }
+
+    #[test]
+    fn test_render_best_tiles() {
+        let input = include_str!("../inputs/16.ex");
+        let maze = Maze::from(input);
+        let rendered = render_best_tiles(&maze, &solve(&maze, Costs::default(), Search::AStar));
+        assert_eq!(
+            rendered,
+            "###############\n\
+             #.......#....E#\n\
+             #.#.###.#.###O#\n\
+             #.....#.#...#O#\n\
+             #.###.#####.#O#\n\
+             #.#.#.......#O#\n\
+             #.#.#####.###O#\n\
+             #..OOOOOOOOO#O#\n\
+             ###O#O#####O#O#\n\
+             #OOO#O....#O#O#\n\
+             #O#O#O###.#O#O#\n\
+             #OOOOO#...#O#O#\n\
+             #O###.#.#.#O#O#\n\
+             #S..#.....#OOO#\n\
+             ###############\n"
+        );
+    }
+
+    #[test]
+    fn test_best_paths() {
+        let input = include_str!("../inputs/16.ex");
+        let maze = Maze::from(input);
+        let solution = solve(&maze, Costs::default(), Search::AStar);
+
+        let paths = solution.best_paths(usize::MAX);
+        assert_eq!(paths.len(), 3);
+
+        let union: HashSet<(isize, isize)> = paths
+            .iter()
+            .flat_map(|path| path.iter().map(|n| (n.x, n.y)))
+            .collect();
+        assert_eq!(union, solution.best_path_tiles());
+
+        for path in paths.iter() {
+            assert_eq!(path[0], maze.start_node);
+            assert_eq!((path.last().unwrap().x, path.last().unwrap().y), maze.end);
+        }
+
+        assert_eq!(solution.best_paths(2).len(), 2);
+        assert_eq!(render_best_paths(&maze, &solution, 10).len(), 3);
+    }
+
+    #[test]
+    fn test_best_paths_free_turns() {
+        // With free turns, turning on the spot can loop back onto a path, but paths should never
+        // repeat a node
+        let input = include_str!("../inputs/16.ex");
+        let maze = Maze::from(input);
+        let costs = Costs { step: 1, turn: 0 };
+
+        for search in [Search::Dijkstra, Search::AStar] {
+            let solution = solve(&maze, costs, search);
+            let paths = solution.best_paths(usize::MAX);
+            assert!(!paths.is_empty());
+
+            for path in paths.iter() {
+                let unique: HashSet<&Node> = path.iter().collect();
+                assert_eq!(unique.len(), path.len());
+
+                let tiles: Vec<(isize, isize)> = path.iter().map(|n| (n.x, n.y)).dedup().collect();
+                assert_eq!(tiles.len() - 1, solution.cost);
+            }
+
+            let union: HashSet<(isize, isize)> = paths
+                .iter()
+                .flat_map(|path| path.iter().map(|n| (n.x, n.y)))
+                .collect();
+            assert_eq!(union, solution.best_path_tiles());
+        }
+    }
+
+    #[test]
+    fn test_search_matches_dijkstra() {
+        let inputs = [
+            include_str!("../inputs/16.ex"),
+            include_str!("../inputs/16_2.ex"),
+        ];
+        let all_costs = [
+            Costs::default(),
+            Costs { step: 1, turn: 0 },
+            Costs { step: 7, turn: 3 },
+        ];
+
+        for input in inputs {
+            let maze = Maze::from(input);
+            for costs in all_costs {
+                let dijkstra = solve(&maze, costs, Search::Dijkstra);
+                let a_star = solve(&maze, costs, Search::AStar);
+                assert_eq!(dijkstra.cost, a_star.cost);
+                assert_eq!(dijkstra.best_path_tiles(), a_star.best_path_tiles());
+            }
+        }
+    }
+
+    #[test]
+    fn test_heuristic() {
+        let costs = Costs::default();
+        let node = |dir, x, y| Node { dir, x, y };
+
+        // At the end, or facing straight at it
+        assert_eq!(heuristic(&node(Dir::West, 3, 3), (3, 3), costs), 0);
+        assert_eq!(heuristic(&node(Dir::East, 1, 3), (3, 3), costs), 2);
+        // Facing sideways or away from an end in a straight line
+        assert_eq!(heuristic(&node(Dir::North, 1, 3), (3, 3), costs), 1002);
+        assert_eq!(heuristic(&node(Dir::West, 1, 3), (3, 3), costs), 2002);
+        // End is diagonal from the node
+        assert_eq!(heuristic(&node(Dir::East, 1, 3), (3, 1), costs), 1004);
+        assert_eq!(heuristic(&node(Dir::South, 1, 3), (3, 1), costs), 2004);
+    }
 }
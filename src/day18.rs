@@ -1,113 +1,329 @@
-use crate::neighbor;
+use crate::neighbor::NeighborIter;
 use priority_queue::PriorityQueue;
-use std::{cmp::Reverse, collections::HashSet, time::Instant};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    time::Instant,
+};
+
+/// Largest coordinate of the memory grid in the puzzle
+pub const MAX_DIM: usize = 70;
+/// Number of bytes which have fallen for part 1 of the puzzle
+pub const SIM_LIMIT: usize = 1024;
 
 pub fn run_outer() -> String {
+    run_outer_with(MAX_DIM, SIM_LIMIT, false, false)
+}
+
+/// Run with a different grid size or number of fallen bytes to the puzzle, and optionally draw
+/// the grid and path for part 1, or list the shortest path length after each byte falls.
+pub fn run_outer_with(
+    max_dim: usize,
+    sim_limit: usize,
+    show_grid: bool,
+    show_lengths: bool,
+) -> String {
     let input = include_str!("../inputs/18.in");
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let (pt1, pt2) = run_inner(input, max_dim, sim_limit);
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
-        pt1,
-        pt2,
+        pt1.map_or("none".to_string(), |len| len.to_string()),
+        pt2.map_or("none".to_string(), |(x, y)| format!("{x},{y}")),
         elapsed.as_micros()
-    )
-}
+    );
+
+    let positions = parse(input);
+    let space = MemorySpace::square(max_dim);
 
-fn run(input: &str) -> (u64, String) {
-    run_inner(input, 70, 1024)
+    if show_grid {
+        let rendered = render_after(&positions, space, sim_limit);
+        write!(out, "\n{}", rendered.trim_end()).unwrap();
+    }
+
+    if show_lengths {
+        for (fallen, length) in path_lengths(&positions, space).iter().enumerate() {
+            match length {
+                Some(length) => write!(out, "\n{fallen}: {length}").unwrap(),
+                None => write!(out, "\n{fallen}: none").unwrap(),
+            }
+        }
+    }
+
+    out
 }
 
+/// Find a shortest path from the start to the goal avoiding the blocked cells, including both ends.
 fn shortest_path(
-    all_positions: &Vec<(usize, usize)>,
-    max_dim: usize,
-    sim_limit: usize,
-) -> Option<u64> {
-    let positions: HashSet<(usize, usize)> =
-        all_positions.iter().take(sim_limit).cloned().collect();
-
+    blocked: &HashSet<(usize, usize)>,
+    space: MemorySpace,
+) -> Option<Vec<(usize, usize)>> {
     let mut unvisited: PriorityQueue<(usize, usize), Reverse<usize>> = PriorityQueue::new();
-    let mut visited: Vec<((usize, usize), usize)> = Vec::new();
-    for x in 0..=max_dim {
-        for y in 0..=max_dim {
+    let mut prev: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    for x in 0..=space.max.0 {
+        for y in 0..=space.max.1 {
             let pos = (x, y);
-            if !positions.contains(&pos) {
+            if !blocked.contains(&pos) {
                 unvisited.push(pos, Reverse(usize::MAX));
             }
         }
     }
 
     // Ensure start node is popped first
-    unvisited.change_priority(&(0, 0), Reverse(0));
+    unvisited.change_priority(&space.start, Reverse(0))?;
 
     // Dijkstra
     while let Some((current, Reverse(cost))) = unvisited.pop() {
-        // Break if only unreachable nodes are rerun_outer() -> Stringing
+        // Break if only unreachable nodes are remaining
         if cost == usize::MAX {
             break;
         }
 
-        for neighbor in neighbor::NeighborIter::new(current, max_dim) {
+        if current == space.goal {
+            // Walk back through the previous nodes to recover the path
+            let mut path = vec![current];
+            while let Some(p) = prev.get(path.last().unwrap()) {
+                path.push(*p);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for neighbor in NeighborIter::with_bounds(current, space.max) {
             let new_cost = cost + 1;
             if let Some(Reverse(existing_cost)) = unvisited.get_priority(&neighbor) {
                 if new_cost < *existing_cost {
                     unvisited.change_priority(&neighbor, Reverse(new_cost));
+                    prev.insert(neighbor, current);
                 }
             }
         }
+    }
 
-        visited.push((current, cost));
+    None
+}
+
+/// Shortest path length after each number of fallen bytes, from none up to all of them.
+///
+/// A path stays shortest until a byte lands on it, since bytes only ever remove cells, so the path
+/// only needs recalculating when that happens. Once the goal is cut off it stays that way.
+fn path_lengths(positions: &[(usize, usize)], space: MemorySpace) -> Vec<Option<u64>> {
+    let mut blocked: HashSet<(usize, usize)> = HashSet::new();
+    let mut path = shortest_path(&blocked, space);
+    let mut on_path: HashSet<(usize, usize)> = path.iter().flatten().copied().collect();
+    let length = |path: &Option<Vec<(usize, usize)>>| path.as_ref().map(|p| p.len() as u64 - 1);
+
+    let mut lengths = vec![length(&path)];
+    for pos in positions {
+        blocked.insert(*pos);
+        if on_path.contains(pos) {
+            path = shortest_path(&blocked, space);
+            on_path = path.iter().flatten().copied().collect();
+        }
+        lengths.push(length(&path));
     }
 
-    // Find end node in visited Vec, and check cost
-    for (pos, cost) in visited.iter() {
-        if *pos == (max_dim, max_dim) {
-            return Some(*cost as u64);
+    lengths
+}
+
+/// Render the memory grid with fallen bytes as `#` and the path as `O`.
+fn render(
+    blocked: &HashSet<(usize, usize)>,
+    space: MemorySpace,
+    path: &[(usize, usize)],
+) -> String {
+    let path: HashSet<&(usize, usize)> = path.iter().collect();
+    let mut out = String::new();
+
+    for y in 0..=space.max.1 {
+        for x in 0..=space.max.0 {
+            let ch = if blocked.contains(&(x, y)) {
+                '#'
+            } else if path.contains(&(x, y)) {
+                'O'
+            } else {
+                '.'
+            };
+            out.push(ch);
         }
+        out.push('\n');
     }
 
-    None
+    out
+}
+
+/// Render the grid after `sim_limit` bytes have fallen, along with a shortest path if one exists.
+fn render_after(positions: &[(usize, usize)], space: MemorySpace, sim_limit: usize) -> String {
+    let blocked: HashSet<(usize, usize)> = positions.iter().take(sim_limit).cloned().collect();
+    let path = shortest_path(&blocked, space).unwrap_or_default();
+    render(&blocked, space, &path)
 }
 
-fn run_inner(input: &str, max_dim: usize, sim_limit: usize) -> (u64, String) {
-    let all_positions: Vec<(usize, usize)> = input
+fn parse(input: &str) -> Vec<(usize, usize)> {
+    input
         .trim()
         .lines()
         .map(|line| {
             let (x, y) = line.split_once(",").unwrap();
             (x.parse().unwrap(), y.parse().unwrap())
         })
-        .collect();
+        .collect()
+}
+
+/// The memory grid, with cells from (0, 0) to `max` inclusive, and the cells the path must join.
+#[derive(Debug, Clone, Copy)]
+struct MemorySpace {
+    max: (usize, usize),
+    start: (usize, usize),
+    goal: (usize, usize),
+}
 
-    let pt1 = shortest_path(&all_positions, max_dim, sim_limit).unwrap();
+impl MemorySpace {
+    /// Square grid from the top left to the bottom right corner, as in the puzzle.
+    fn square(max_dim: usize) -> Self {
+        Self {
+            max: (max_dim, max_dim),
+            start: (0, 0),
+            goal: (max_dim, max_dim),
+        }
+    }
 
-    // For part 2, binary search the input to find the point at which the end becomes unreachable
-    let mut low = 0;
-    let mut high = all_positions.len();
+    fn cell_count(&self) -> usize {
+        (self.max.0 + 1) * (self.max.1 + 1)
+    }
 
-    while low < high {
-        let mid = (low + high) / 2;
+    /// Whether `pos` is inside the grid. Bytes falling outside it don't block anything.
+    fn contains(&self, pos: (usize, usize)) -> bool {
+        pos.0 <= self.max.0 && pos.1 <= self.max.1
+    }
+
+    fn index(&self, pos: (usize, usize)) -> usize {
+        pos.1 * (self.max.0 + 1) + pos.0
+    }
+}
 
-        if let Some(_) = shortest_path(&all_positions, max_dim, mid) {
-            // Possible to reach end, look again in high side
-            low = mid + 1;
-        } else {
-            // Not possible to reach end, look again in low side
-            high = mid;
+/// Union-find over grid cells, with path halving and union by size.
+struct DisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
         }
     }
 
-    // Double check that we have the right value...
-    assert!(shortest_path(&all_positions, max_dim, low).is_none());
-    assert!(shortest_path(&all_positions, max_dim, low - 1).is_some());
+    fn find(&mut self, mut idx: usize) -> usize {
+        while self.parent[idx] != idx {
+            self.parent[idx] = self.parent[self.parent[idx]];
+            idx = self.parent[idx];
+        }
 
-    // We found the length of input at which the end becomes unreachable. The index at which the value
-    // causing the end to be unreachable lies is 1 less than this.
-    let pt2 = all_positions[low - 1];
-    let pt2_fmt = format!("{},{}", pt2.0, pt2.1);
+        idx
+    }
 
-    (pt1, pt2_fmt)
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+
+/// Find the index of the first byte after which the goal can no longer be reached from the start,
+/// or `None` if no byte is responsible (the goal is reachable once every byte has fallen, or was
+/// never reachable to begin with).
+///
+/// Rather than searching for a path after each byte, this starts with every byte fallen and
+/// removes them again in reverse, joining each freed cell to its free neighbors. The first byte
+/// whose removal connects the start and the goal is the one which blocked the path.
+fn first_blocking_byte(positions: &[(usize, usize)], space: MemorySpace) -> Option<usize> {
+    // The time each cell is first blocked, if ever. Bytes landing on an already blocked cell
+    // change nothing, so only the first one matters.
+    let mut blocked_at: Vec<Option<usize>> = vec![None; space.cell_count()];
+    for (i, pos) in positions.iter().enumerate() {
+        if !space.contains(*pos) {
+            continue;
+        }
+
+        let cell = &mut blocked_at[space.index(*pos)];
+        if cell.is_none() {
+            *cell = Some(i);
+        }
+    }
+
+    let mut free: Vec<bool> = blocked_at.iter().map(|b| b.is_none()).collect();
+    let mut sets = DisjointSet::new(space.cell_count());
+
+    let join_free_neighbors = |sets: &mut DisjointSet, free: &[bool], pos: (usize, usize)| {
+        for neighbor in NeighborIter::with_bounds(pos, space.max) {
+            if free[space.index(neighbor)] {
+                sets.union(space.index(pos), space.index(neighbor));
+            }
+        }
+    };
+
+    for y in 0..=space.max.1 {
+        for x in 0..=space.max.0 {
+            if free[space.index((x, y))] {
+                join_free_neighbors(&mut sets, &free, (x, y));
+            }
+        }
+    }
+
+    let (start, goal) = (space.index(space.start), space.index(space.goal));
+    let connected = |sets: &mut DisjointSet, free: &[bool]| {
+        free[start] && free[goal] && sets.find(start) == sets.find(goal)
+    };
+
+    if connected(&mut sets, &free) {
+        return None;
+    }
+
+    for (i, pos) in positions.iter().enumerate().rev() {
+        if !space.contains(*pos) || blocked_at[space.index(*pos)] != Some(i) {
+            continue;
+        }
+
+        free[space.index(*pos)] = true;
+        join_free_neighbors(&mut sets, &free, *pos);
+
+        if connected(&mut sets, &free) {
+            return Some(i);
+        }
+    }
+
+    // The goal is unreachable even before any bytes have fallen
+    None
+}
+
+/// The shortest path length after `sim_limit` bytes, and the first byte to cut off the goal.
+/// Either can be `None` with a grid or number of bytes other than the puzzle's.
+fn run_inner(
+    input: &str,
+    max_dim: usize,
+    sim_limit: usize,
+) -> (Option<u64>, Option<(usize, usize)>) {
+    let all_positions = parse(input);
+    let space = MemorySpace::square(max_dim);
+
+    let blocked: HashSet<(usize, usize)> = all_positions.iter().take(sim_limit).cloned().collect();
+    let pt1 = shortest_path(&blocked, space).map(|path| path.len() as u64 - 1);
+
+    let pt2 = first_blocking_byte(&all_positions, space).map(|idx| all_positions[idx]);
+
+    (pt1, pt2)
 }
 
 #[cfg(test)]
@@ -118,7 +334,99 @@ mod test {
     fn test_example() {
         let input = include_str!("../inputs/18.ex");
         let (pt1, pt2) = run_inner(&input, 6, 12);
-        assert_eq!(pt1, 22);
-        assert_eq!(pt2, "6,1");
+        assert_eq!(pt1, Some(22));
+        assert_eq!(pt2, Some((6, 1)));
+
+        // A bigger grid is never cut off, and most bytes miss a tiny one
+        assert_eq!(run_inner(input, 100, 12), (Some(204), None));
+        assert_eq!(run_inner(input, 1, 12), (Some(2), Some((1, 1))));
+        assert_eq!(run_inner(input, 1, usize::MAX), (None, Some((1, 1))));
+    }
+
+    #[test]
+    fn test_first_blocking_byte() {
+        // Five wide and two high
+        let space = MemorySpace {
+            max: (4, 1),
+            start: (0, 0),
+            goal: (4, 1),
+        };
+        let positions = [(1, 0), (2, 1), (3, 0), (1, 1)];
+        assert_eq!(first_blocking_byte(&positions, space), Some(1));
+
+        // Repeated bytes don't unblock a cell until the first of them is removed
+        let positions = [(1, 0), (1, 0), (2, 1)];
+        assert_eq!(first_blocking_byte(&positions, space), Some(2));
+
+        // Never blocked
+        let positions = [(1, 0), (3, 1)];
+        assert_eq!(first_blocking_byte(&positions, space), None);
+
+        // Bytes outside the grid are ignored, rather than wrapping onto another row
+        let space = MemorySpace::square(2);
+        let positions = [(1, 0), (3, 0), (9, 9), (1, 1)];
+        assert_eq!(first_blocking_byte(&positions, space), None);
+        let positions = [(1, 0), (3, 0), (9, 9), (1, 1), (0, 1)];
+        assert_eq!(first_blocking_byte(&positions, space), Some(4));
+    }
+
+    #[test]
+    fn test_first_blocking_byte_start_goal() {
+        let space = MemorySpace {
+            max: (4, 1),
+            start: (2, 0),
+            goal: (2, 1),
+        };
+
+        // A byte falling on the goal blocks it, regardless of the rest of the grid
+        let positions = [(0, 0), (2, 1), (3, 1)];
+        assert_eq!(first_blocking_byte(&positions, space), Some(1));
+
+        let positions = [(1, 0), (1, 1), (3, 0), (3, 1)];
+        assert_eq!(first_blocking_byte(&positions, space), None);
+    }
+
+    #[test]
+    fn test_path_lengths() {
+        let input = include_str!("../inputs/18.ex");
+        let positions = parse(input);
+        let space = MemorySpace::square(6);
+
+        let lengths = path_lengths(&positions, space);
+        assert_eq!(lengths.len(), positions.len() + 1);
+        assert_eq!(lengths[0], Some(12));
+        assert_eq!(lengths[12], Some(22));
+
+        // Check against finding the path from scratch after every byte
+        for (k, length) in lengths.iter().enumerate() {
+            let blocked: HashSet<(usize, usize)> = positions.iter().take(k).cloned().collect();
+            let expected = shortest_path(&blocked, space).map(|p| p.len() as u64 - 1);
+            assert_eq!(*length, expected);
+        }
+
+        // The path is lost with the first blocking byte
+        let idx = first_blocking_byte(&positions, space).unwrap();
+        assert!(lengths[idx].is_some());
+        assert!(lengths[idx + 1].is_none());
+    }
+
+    #[test]
+    fn test_render() {
+        let input = include_str!("../inputs/18.ex");
+        let positions = parse(input);
+        let space = MemorySpace::square(6);
+        let rendered = render_after(&positions, space, 12);
+
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines.iter().all(|line| line.len() == 7));
+        assert_eq!(rendered.matches('#').count(), 12);
+        assert_eq!(rendered.matches('O').count(), 23);
+        assert_eq!(&lines[0][..1], "O");
+        assert_eq!(&lines[6][6..], "O");
+
+        // No path once the goal has been cut off
+        let rendered = render_after(&positions, space, positions.len());
+        assert_eq!(rendered.matches('O').count(), 0);
     }
 }
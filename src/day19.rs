@@ -1,25 +1,122 @@
-use std::{collections::HashMap, time::Instant};
+use std::{collections::HashMap, fmt::Write, fs, io, path::Path, time::Instant};
+
+/// Number of arrangements of a single design listed by default
+pub const ARRANGEMENT_LIMIT: usize = 10;
 
 pub fn run_outer() -> String {
+    run_outer_with(None, None, ARRANGEMENT_LIMIT)
+}
+
+/// Run the puzzle, and optionally list the cheapest arrangement of each design using the towel
+/// costs in the file at `costs_path` along with the one using the fewest towels, or up to `limit`
+/// of the arrangements of `design`.
+pub fn run_outer_with(costs_path: Option<&Path>, design: Option<&str>, limit: usize) -> String {
     let input = include_str!("../inputs/19.in");
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let (pt1, pt2) = run(input);
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
         pt1,
         pt2,
         elapsed.as_micros()
-    )
+    );
+
+    if let Some(costs_path) = costs_path {
+        match describe_cheapest_designs(input, costs_path) {
+            Ok(designs) => out.push_str(&designs),
+            Err(e) => write!(out, "\nfailed to read towel costs: {e}").unwrap(),
+        }
+    }
+
+    if let Some(design) = design {
+        out.push_str(&describe_arrangements(input, design, limit));
+    }
+
+    out
+}
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: HashMap<u8, usize>,
+    /// Index of the towel ending at this node, if any
+    towel: Option<usize>,
+}
+
+/// Prefix tree of towels, so that all towels matching the start of a pattern can be found in a
+/// single pass over the pattern.
+#[derive(Debug)]
+struct Trie {
+    nodes: Vec<TrieNode>,
+}
+
+impl Trie {
+    fn new() -> Self {
+        Self {
+            nodes: vec![TrieNode::default()],
+        }
+    }
+
+    fn insert(&mut self, towel: &str, idx: usize) {
+        let mut node = 0;
+        for b in towel.bytes() {
+            node = match self.nodes[node].children.get(&b) {
+                Some(next) => *next,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let next = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(b, next);
+                    next
+                }
+            };
+        }
+        self.nodes[node].towel = Some(idx);
+    }
+
+    /// Indices of all towels which are a prefix of `pattern`, shortest first.
+    fn prefixes<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = usize> + 'a {
+        let mut node = 0;
+        pattern
+            .bytes()
+            .map_while(move |b| {
+                node = *self.nodes[node].children.get(&b)?;
+                Some(self.nodes[node].towel)
+            })
+            .flatten()
+    }
+}
+
+struct Towels<'a> {
+    towels: Vec<&'a str>,
+    trie: Trie,
+}
+
+impl<'a> From<&'a str> for Towels<'a> {
+    fn from(s: &'a str) -> Self {
+        let towels: Vec<&str> = s.split(", ").collect();
+        let mut trie = Trie::new();
+        for (i, towel) in towels.iter().enumerate() {
+            trie.insert(towel, i);
+        }
+
+        Self { towels, trie }
+    }
+}
+
+impl<'a> Towels<'a> {
+    /// All towels which match the start of `pattern`, shortest first.
+    fn matches<'b>(&'b self, pattern: &'b str) -> impl Iterator<Item = &'a str> + 'b {
+        self.trie.prefixes(pattern).map(|i| self.towels[i])
+    }
 }
 
 fn pattern_count_combinations<'a>(
     pattern: &'a str,
-    towels: &Vec<&str>,
+    towels: &Towels,
     cache: &mut HashMap<&'a str, u64>,
 ) -> u64 {
     // Recursion end condition
-    if pattern.len() == 0 {
+    if pattern.is_empty() {
         return 1;
     }
 
@@ -29,21 +126,218 @@ fn pattern_count_combinations<'a>(
     }
 
     let mut combinations = 0;
-    for towel in towels.iter() {
-        if pattern.starts_with(towel) {
-            let next_slice = &pattern[towel.len()..];
-            combinations += pattern_count_combinations(next_slice, towels, cache);
-        }
+    for towel in towels.matches(pattern) {
+        let next_slice = &pattern[towel.len()..];
+        combinations += pattern_count_combinations(next_slice, towels, cache);
     }
 
     cache.insert(pattern, combinations);
-    return combinations;
+    combinations
+}
+
+/// Lazily enumerates every arrangement of towels which makes up a pattern.
+struct Arrangements<'a, 'b> {
+    towels: &'b Towels<'a>,
+    pattern: &'a str,
+    cache: HashMap<&'a str, u64>,
+    /// Position reached in the pattern, and the towels used to get there
+    stack: Vec<(usize, Vec<&'a str>)>,
+}
+
+impl<'a, 'b> Arrangements<'a, 'b> {
+    fn new(pattern: &'a str, towels: &'b Towels<'a>) -> Self {
+        Self {
+            towels,
+            pattern,
+            cache: HashMap::new(),
+            stack: vec![(0, Vec::new())],
+        }
+    }
+}
+
+impl<'a> Iterator for Arrangements<'a, '_> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((pos, used)) = self.stack.pop() {
+            if pos == self.pattern.len() {
+                return Some(used);
+            }
+
+            // Only follow towels which leave a remainder that can still be made, so that every
+            // branch explored leads to an arrangement. Push in reverse so that the shortest towel
+            // is tried first.
+            let rest = &self.pattern[pos..];
+            let next: Vec<&str> = self.towels.matches(rest).collect();
+            for towel in next.into_iter().rev() {
+                let next_pos = pos + towel.len();
+                let remainder = &self.pattern[next_pos..];
+                if pattern_count_combinations(remainder, self.towels, &mut self.cache) > 0 {
+                    let mut next_used = used.clone();
+                    next_used.push(towel);
+                    self.stack.push((next_pos, next_used));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Up to `n` of the ways the towels can be arranged to make `pattern`.
+fn arrangements<'a>(input: &'a str, pattern: &'a str, n: usize) -> Vec<Vec<&'a str>> {
+    let (towel_line, _) = input.trim().split_once("\n\n").unwrap();
+    let towels = Towels::from(towel_line);
+    Arrangements::new(pattern, &towels).take(n).collect()
+}
+
+/// One line per arrangement of `design`, up to `limit` of them.
+fn describe_arrangements(input: &str, design: &str, limit: usize) -> String {
+    let found = arrangements(input, design, limit);
+    if found.is_empty() {
+        return format!("\n{design} can't be made");
+    }
+
+    let mut out = String::new();
+    for arrangement in found {
+        write!(out, "\n{design}: {}", arrangement.join(",")).unwrap();
+    }
+    out
+}
+
+/// Total cost of an arrangement, along with the towels in it
+type CostedArrangement<'a> = (u64, Vec<&'a str>);
+
+/// Lowest total cost of making `pattern`, where towels without a cost can't be used. The cache
+/// records the first towel of a cheapest arrangement of each suffix, so it can be rebuilt.
+fn pattern_min_cost<'a>(
+    pattern: &'a str,
+    towels: &Towels<'a>,
+    cost: &impl Fn(&str) -> Option<u64>,
+    cache: &mut HashMap<&'a str, Option<(u64, &'a str)>>,
+) -> Option<u64> {
+    // Recursion end condition
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    // Check for result in cache first
+    if let Some(res) = cache.get(pattern) {
+        return res.map(|(total, _)| total);
+    }
+
+    let mut best: Option<(u64, &str)> = None;
+    for towel in towels.matches(pattern) {
+        let Some(towel_cost) = cost(towel) else {
+            continue;
+        };
+
+        let next_slice = &pattern[towel.len()..];
+        if let Some(rest) = pattern_min_cost(next_slice, towels, cost, cache) {
+            let total = towel_cost + rest;
+            if best.is_none_or(|(best_total, _)| total < best_total) {
+                best = Some((total, towel));
+            }
+        }
+    }
+
+    cache.insert(pattern, best);
+    best.map(|(total, _)| total)
+}
+
+/// The cheapest arrangement of towels to make `pattern`, and its total cost.
+fn cheapest_arrangement<'a>(
+    pattern: &'a str,
+    towels: &Towels<'a>,
+    cost: impl Fn(&str) -> Option<u64>,
+) -> Option<CostedArrangement<'a>> {
+    let mut cache = HashMap::new();
+    let total = pattern_min_cost(pattern, towels, &cost, &mut cache)?;
+
+    let mut arrangement = Vec::new();
+    let mut rest = pattern;
+    while !rest.is_empty() {
+        let (_, towel) = cache[rest].expect("Every suffix on the cheapest path can be made");
+        arrangement.push(towel);
+        rest = &rest[towel.len()..];
+    }
+
+    Some((total, arrangement))
+}
+
+/// The arrangement using the fewest towels to make `pattern`.
+fn fewest_towels<'a>(pattern: &'a str, towels: &Towels<'a>) -> Option<Vec<&'a str>> {
+    cheapest_arrangement(pattern, towels, |_| Some(1)).map(|(_, arrangement)| arrangement)
+}
+
+/// Parse a table of towel costs, with one `towel: cost` entry per line.
+fn parse_costs(s: &str) -> io::Result<HashMap<&str, u64>> {
+    s.trim()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            line.split_once(": ")
+                .and_then(|(towel, cost)| Some((towel, cost.trim().parse().ok()?)))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid towel cost on line {}: {line:?}", i + 1),
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Cheapest arrangement for each design, using the costs in the file at `costs_path`. Designs which
+/// can't be made from the towels with a cost are `None`.
+fn cheapest_designs<'a>(
+    input: &'a str,
+    costs_path: &Path,
+) -> io::Result<Vec<(&'a str, Option<CostedArrangement<'a>>)>> {
+    let costs_input = fs::read_to_string(costs_path)?;
+    let costs = parse_costs(&costs_input)?;
+
+    let (towel_line, pattern_lines) = input.trim().split_once("\n\n").unwrap();
+    let towels = Towels::from(towel_line);
+
+    Ok(pattern_lines
+        .lines()
+        .map(|line| {
+            let arrangement =
+                cheapest_arrangement(line, &towels, |towel| costs.get(towel).copied());
+            (line, arrangement)
+        })
+        .collect())
+}
+
+/// One line per design, with its cheapest arrangement and cost, and the arrangement using the
+/// fewest towels.
+fn describe_cheapest_designs(input: &str, costs_path: &Path) -> io::Result<String> {
+    let (towel_line, _) = input.trim().split_once("\n\n").unwrap();
+    let towels = Towels::from(towel_line);
+    let mut out = String::new();
+
+    for (design, arrangement) in cheapest_designs(input, costs_path)? {
+        write!(out, "\n{design}: cheapest ").unwrap();
+        match arrangement {
+            Some((total, used)) => write!(out, "{total} ({})", used.join(",")).unwrap(),
+            None => out.push_str("impossible"),
+        }
+
+        out.push_str(", fewest ");
+        match fewest_towels(design, &towels) {
+            Some(used) => write!(out, "{} ({})", used.len(), used.join(",")).unwrap(),
+            None => out.push_str("impossible"),
+        }
+    }
+
+    Ok(out)
 }
 
 fn run(input: &str) -> (u64, u64) {
     let (towel_line, pattern_lines) = input.trim().split_once("\n\n").unwrap();
 
-    let towels: Vec<&str> = towel_line.split(", ").collect();
+    let towels = Towels::from(towel_line);
 
     let mut cache = HashMap::<&str, u64>::new();
 
@@ -73,4 +367,136 @@ mod test {
         assert_eq!(pt1, 6);
         assert_eq!(pt2, 16);
     }
+
+    #[test]
+    fn test_trie() {
+        let towels = Towels::from("r, wr, b, g, bwu, rb, gb, br");
+
+        let actual: Vec<&str> = towels.matches("brwrr").collect();
+        assert_eq!(actual, vec!["b", "br"]);
+
+        let actual: Vec<&str> = towels.matches("bwurrg").collect();
+        assert_eq!(actual, vec!["b", "bwu"]);
+
+        assert_eq!(towels.matches("ubwu").count(), 0);
+    }
+
+    #[test]
+    fn test_arrangements() {
+        let input = include_str!("../inputs/19.ex");
+        assert_eq!(
+            arrangements(input, "brwrr", 10),
+            vec![vec!["b", "r", "wr", "r"], vec!["br", "wr", "r"]]
+        );
+        assert_eq!(arrangements(input, "rrbgbr", 2).len(), 2);
+        assert!(arrangements(input, "ubwu", 10).is_empty());
+
+        assert_eq!(
+            describe_arrangements(input, "brwrr", 1),
+            "\nbrwrr: b,r,wr,r"
+        );
+        assert_eq!(
+            describe_arrangements(input, "ubwu", 10),
+            "\nubwu can't be made"
+        );
+
+        // Enumerating all arrangements agrees with counting them
+        let (towel_line, pattern_lines) = input.trim().split_once("\n\n").unwrap();
+        let towels = Towels::from(towel_line);
+        let mut cache = HashMap::new();
+        for pattern in pattern_lines.lines() {
+            let all: Vec<Vec<&str>> = Arrangements::new(pattern, &towels).collect();
+            assert_eq!(
+                all.len() as u64,
+                pattern_count_combinations(pattern, &towels, &mut cache)
+            );
+            assert!(all.iter().all(|a| a.concat() == pattern));
+        }
+    }
+
+    #[test]
+    fn test_fewest_towels() {
+        let input = include_str!("../inputs/19.ex");
+        let (towel_line, pattern_lines) = input.trim().split_once("\n\n").unwrap();
+        let towels = Towels::from(towel_line);
+
+        let actual: Vec<Option<usize>> = pattern_lines
+            .lines()
+            .map(|line| fewest_towels(line, &towels).map(|a| a.len()))
+            .collect();
+        let expected = vec![
+            Some(3),
+            Some(4),
+            Some(2),
+            Some(4),
+            None,
+            Some(4),
+            Some(3),
+            None,
+        ];
+        assert_eq!(actual, expected);
+
+        assert_eq!(fewest_towels("gbbr", &towels), Some(vec!["gb", "br"]));
+    }
+
+    #[test]
+    fn test_cheapest_arrangement() {
+        let towels = Towels::from("r, wr, b, g, bwu, rb, gb, br");
+
+        let costs = parse_costs("r: 1\nwr: 1\nb: 1\ng: 1\nbwu: 1\nrb: 1\ngb: 1\nbr: 10\n").unwrap();
+        assert_eq!(costs.len(), 8);
+        let cost = |towel: &str| costs.get(towel).copied();
+        assert_eq!(
+            cheapest_arrangement("brwrr", &towels, cost),
+            Some((4, vec!["b", "r", "wr", "r"]))
+        );
+
+        // Towels missing from the table can't be used
+        let costs = parse_costs("r: 1\nwr: 1\nbr: 10").unwrap();
+        let cost = |towel: &str| costs.get(towel).copied();
+        assert_eq!(
+            cheapest_arrangement("brwrr", &towels, cost),
+            Some((12, vec!["br", "wr", "r"]))
+        );
+        assert_eq!(cheapest_arrangement("bggr", &towels, cost), None);
+    }
+
+    #[test]
+    fn test_cheapest_designs() {
+        let input = include_str!("../inputs/19.ex");
+        let dir = std::env::temp_dir().join("aoc2024_day19_costs");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("costs.txt");
+        fs::write(
+            &path,
+            "r: 1\nwr: 2\nb: 1\ng: 1\nbwu: 5\nrb: 3\ngb: 1\nbr: 1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            describe_cheapest_designs(input, &path).unwrap(),
+            "\nbrwrr: cheapest 4 (br,wr,r), fewest 3 (br,wr,r)\n\
+             bggr: cheapest 4 (b,g,g,r), fewest 4 (b,g,g,r)\n\
+             gbbr: cheapest 2 (gb,br), fewest 2 (gb,br)\n\
+             rrbgbr: cheapest 5 (r,r,b,g,br), fewest 4 (r,rb,g,br)\n\
+             ubwu: cheapest impossible, fewest impossible\n\
+             bwurrg: cheapest 8 (bwu,r,r,g), fewest 4 (bwu,r,r,g)\n\
+             brgr: cheapest 3 (br,g,r), fewest 3 (br,g,r)\n\
+             bbrgwb: cheapest impossible, fewest impossible"
+        );
+
+        // A malformed table is an error rather than a panic
+        fs::write(&path, "r: 1\nwr two\n").unwrap();
+        let err = cheapest_designs(input, &path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        fs::write(&path, "r: 1\nwr: two\n").unwrap();
+        assert!(describe_cheapest_designs(input, &path).is_err());
+
+        // As is a missing one
+        let err = cheapest_designs(input, &dir.join("missing.txt")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,58 +1,154 @@
 use crate::neighbor::NeighborIter;
 use priority_queue::PriorityQueue;
-use std::{cmp::Reverse, collections::HashMap, time::Instant};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    time::Instant,
+};
+
+/// Longest cheat allowed in part 1 of the puzzle
+pub const PT1_CHEAT_TIME: usize = 2;
+/// Longest cheat allowed in part 2 of the puzzle
+pub const PT2_CHEAT_TIME: usize = 20;
+/// Minimum time a cheat must save to be counted in the puzzle
+pub const THRESHOLD: u32 = 100;
 
 pub fn run_outer() -> String {
+    run_outer_with(PT1_CHEAT_TIME, PT2_CHEAT_TIME, THRESHOLD, false, false)
+}
+
+/// Run with different cheat lengths or minimum time saved to the puzzle, and optionally show how
+/// many cheats save each amount of time, or list every cheat which saves at least `threshold`.
+pub fn run_outer_with(
+    pt1_cheat_time: usize,
+    pt2_cheat_time: usize,
+    threshold: u32,
+    show_histogram: bool,
+    list_cheats: bool,
+) -> String {
     let input = include_str!("../inputs/20.in");
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let pt1 = run_inner(input, pt1_cheat_time, threshold);
+    let pt2 = run_inner(input, pt2_cheat_time, threshold);
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
         pt1,
         pt2,
         elapsed.as_micros()
-    )
+    );
+
+    for (part, cheat_time) in [("pt1", pt1_cheat_time), ("pt2", pt2_cheat_time)] {
+        out.push_str(&report(
+            input,
+            part,
+            cheat_time,
+            threshold,
+            show_histogram,
+            list_cheats,
+        ));
+    }
+
+    out
 }
 
-fn run(input: &str) -> (u64, u64) {
-    let pt1 = run_inner(input, 2, 100);
-    let pt2 = run_inner(input, 20, 100);
-    (pt1, pt2)
+/// The histogram and list of cheats for one part, each only if asked for.
+fn report(
+    input: &str,
+    part: &str,
+    cheat_time: usize,
+    threshold: u32,
+    show_histogram: bool,
+    list_cheats: bool,
+) -> String {
+    let mut out = String::new();
+
+    if show_histogram {
+        let histogram = histogram(input, cheat_time);
+        write!(out, "\n{part} histogram:").unwrap();
+        for line in format_histogram(&histogram, threshold).lines() {
+            write!(out, "\n{line}").unwrap();
+        }
+    }
+
+    if list_cheats {
+        write!(out, "\n{part} cheats:").unwrap();
+        for cheat in qualifying_cheats(input, cheat_time, threshold) {
+            write!(
+                out,
+                "\n{},{} -> {},{} saves {}",
+                cheat.start.0, cheat.start.1, cheat.end.0, cheat.end.1, cheat.saved
+            )
+            .unwrap();
+        }
+    }
+
+    out
 }
 
-fn run_inner(input: &str, cheat_time: usize, threshold: u32) -> u64 {
-    let mut end = (0, 0);
-    let mut unvisited: PriorityQueue<(usize, usize), Reverse<u32>> = PriorityQueue::new();
-    let mut wall_tiles: Vec<(usize, usize)> = Vec::new();
-
-    for (y, line) in input.trim().lines().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            match c {
-                '.' => {
-                    unvisited.push((x, y), Reverse(u32::MAX));
-                }
-                'S' => {
-                    unvisited.push((x, y), Reverse(0));
-                }
-                'E' => {
-                    unvisited.push((x, y), Reverse(u32::MAX));
-                    end = (x, y);
-                }
-                '#' => {
-                    wall_tiles.push((x, y));
-                }
-                _ => {}
-            };
+/// A cheat from a position on the track to a later one, skipping the track in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Cheat {
+    start: (usize, usize),
+    end: (usize, usize),
+    saved: u32,
+}
+
+/// Times taken to get between each position on the course and the start and end, without any
+/// cheats.
+struct Track {
+    from_start: HashMap<(usize, usize), u32>,
+    to_end: HashMap<(usize, usize), u32>,
+    /// Time taken to finish the course without cheating
+    best: u32,
+}
+
+impl From<&str> for Track {
+    fn from(input: &str) -> Self {
+        let mut start = (0, 0);
+        let mut end = (0, 0);
+        let mut open: Vec<(usize, usize)> = Vec::new();
+
+        for (y, line) in input.trim().lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    '.' => open.push((x, y)),
+                    'S' => {
+                        open.push((x, y));
+                        start = (x, y);
+                    }
+                    'E' => {
+                        open.push((x, y));
+                        end = (x, y);
+                    }
+                    _ => {}
+                };
+            }
+        }
+
+        let from_start = costs_from(&open, start);
+        let to_end = costs_from(&open, end);
+        let best = *from_start.get(&end).expect("End should be reachable");
+
+        Track {
+            from_start,
+            to_end,
+            best,
         }
     }
+}
+
+/// Find the time taken to get to each reachable open position from `origin`.
+fn costs_from(open: &[(usize, usize)], origin: (usize, usize)) -> HashMap<(usize, usize), u32> {
+    let mut unvisited: PriorityQueue<(usize, usize), Reverse<u32>> =
+        open.iter().map(|pos| (*pos, Reverse(u32::MAX))).collect();
+    unvisited.change_priority(&origin, Reverse(0));
 
-    // Find the path through the course without any cheats, recording the time taken to get to
-    // each position.
     let mut visited: HashMap<(usize, usize), u32> = HashMap::new();
 
     while let Some((current, Reverse(cost))) = unvisited.pop() {
-        // Break if all rerun_outer() -> Stringing nodes are unreachable
+        // Break if all remaining nodes are unreachable
         if cost == u32::MAX {
             break;
         }
@@ -72,44 +168,70 @@ fn run_inner(input: &str, cheat_time: usize, threshold: u32) -> u64 {
         visited.insert(current, cost);
     }
 
-    // Verify that the end node has the maximum cost. If not, then we need to think again about how
-    // to check the time saved by a shortcut...
-    let mut max_cost = 0;
-    let mut max_cost_pos = (0, 0);
-    for (k, v) in visited.iter() {
-        if *v > max_cost {
-            max_cost = *v;
-            max_cost_pos = *k;
-        }
+    visited
+}
+
+/// All cheats of at most `cheat_time` which save any time at all.
+fn cheats(track: &Track, cheat_time: usize) -> impl Iterator<Item = Cheat> + '_ {
+    // For each position reachable from the start, check which positions that can reach the end are
+    // within the cheat time (by moving freely anywhere on the grid, not just along the track). The
+    // time taken with the cheat is the time to get to the first position, plus the time to get
+    // from the second to the end, plus the length of the cheat itself.
+    track.from_start.iter().flat_map(move |(pos, cost)| {
+        points_within_distance(*pos, cheat_time).filter_map(move |other| {
+            let other_cost = track.to_end.get(&other)?;
+            let total = cost + distance(pos, &other) as u32 + other_cost;
+
+            (total < track.best).then(|| Cheat {
+                start: *pos,
+                end: other,
+                saved: track.best - total,
+            })
+        })
+    })
+}
+
+/// Number of cheats saving each amount of time.
+fn histogram(input: &str, cheat_time: usize) -> BTreeMap<u32, usize> {
+    let track = Track::from(input);
+    let mut res = BTreeMap::new();
+    for cheat in cheats(&track, cheat_time) {
+        *res.entry(cheat.saved).or_default() += 1;
     }
-    assert_eq!(max_cost_pos, end);
-
-    // For each position that has been visited, check which of the other visited positions can be
-    // reached within the cheat time (by moving freely anywhere on the grid, not just along points
-    // part of the path). For each of these reachable positions, check the time saving (if any).
-    let mut res = 0;
-    for (pos, cost) in visited.iter() {
-        for other in points_within_distance(*pos, cheat_time) {
-            // Check if the other point is reachable from course
-            if let Some(other_cost) = visited.get(&other) {
-                let d = distance(&pos, &other);
-
-                // We can only save time if the other point has a lower cost than this point AND
-                // this difference in cost is greater than the distance travelled via the shortcut
-                let new_other_cost = other_cost + d as u32;
-                if new_other_cost < *cost {
-                    let time_saved = cost - new_other_cost;
-                    if time_saved >= threshold {
-                        res += 1;
-                    }
-                }
+    res
+}
+
+/// Describe the histogram in the same way as the puzzle text, from the smallest saving up.
+fn format_histogram(histogram: &BTreeMap<u32, usize>, threshold: u32) -> String {
+    histogram
+        .range(threshold..)
+        .map(|(saved, count)| {
+            if *count == 1 {
+                format!("There is one cheat that saves {saved} picoseconds.\n")
+            } else {
+                format!("There are {count} cheats that save {saved} picoseconds.\n")
             }
-        }
-    }
+        })
+        .collect()
+}
 
+/// Every cheat saving at least `threshold`, ordered by where it starts and ends.
+fn qualifying_cheats(input: &str, cheat_time: usize, threshold: u32) -> Vec<Cheat> {
+    let track = Track::from(input);
+    let mut res: Vec<Cheat> = cheats(&track, cheat_time)
+        .filter(|cheat| cheat.saved >= threshold)
+        .collect();
+    res.sort();
     res
 }
 
+fn run_inner(input: &str, cheat_time: usize, threshold: u32) -> u64 {
+    let track = Track::from(input);
+    cheats(&track, cheat_time)
+        .filter(|cheat| cheat.saved >= threshold)
+        .count() as u64
+}
+
 fn distance(a: &(usize, usize), b: &(usize, usize)) -> usize {
     a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
 }
@@ -178,4 +300,124 @@ mod test {
         expected.sort();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_histogram() {
+        let input = include_str!("../inputs/20.ex");
+
+        let expected = BTreeMap::from([
+            (2, 14),
+            (4, 14),
+            (6, 2),
+            (8, 4),
+            (10, 2),
+            (12, 3),
+            (20, 1),
+            (36, 1),
+            (38, 1),
+            (40, 1),
+            (64, 1),
+        ]);
+        assert_eq!(histogram(input, 2), expected);
+
+        let actual: BTreeMap<u32, usize> = histogram(input, 20)
+            .into_iter()
+            .filter(|(saved, _)| *saved >= 50)
+            .collect();
+        let expected = BTreeMap::from([
+            (50, 32),
+            (52, 31),
+            (54, 29),
+            (56, 39),
+            (58, 25),
+            (60, 23),
+            (62, 20),
+            (64, 19),
+            (66, 12),
+            (68, 14),
+            (70, 12),
+            (72, 22),
+            (74, 4),
+            (76, 3),
+        ]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_histogram() {
+        let input = include_str!("../inputs/20.ex");
+        let formatted = format_histogram(&histogram(input, 2), 12);
+        assert_eq!(
+            formatted,
+            "There are 3 cheats that save 12 picoseconds.\n\
+             There is one cheat that saves 20 picoseconds.\n\
+             There is one cheat that saves 36 picoseconds.\n\
+             There is one cheat that saves 38 picoseconds.\n\
+             There is one cheat that saves 40 picoseconds.\n\
+             There is one cheat that saves 64 picoseconds.\n"
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let input = include_str!("../inputs/20.ex");
+        assert_eq!(report(input, "pt1", 2, 38, false, false), "");
+        assert_eq!(
+            report(input, "pt1", 2, 38, true, true),
+            "\npt1 histogram:\n\
+             There is one cheat that saves 38 picoseconds.\n\
+             There is one cheat that saves 40 picoseconds.\n\
+             There is one cheat that saves 64 picoseconds.\n\
+             pt1 cheats:\n\
+             7,7 -> 5,7 saves 64\n\
+             7,7 -> 7,9 saves 40\n\
+             8,7 -> 8,9 saves 38"
+        );
+    }
+
+    #[test]
+    fn test_qualifying_cheats() {
+        let input = include_str!("../inputs/20.ex");
+
+        // The single best cheat in part 1 is through the wall in the middle of the example
+        let actual = qualifying_cheats(input, 2, 64);
+        assert_eq!(
+            actual,
+            vec![Cheat {
+                start: (7, 7),
+                end: (5, 7),
+                saved: 64
+            }]
+        );
+
+        // All cheats which qualify are counted
+        let actual = qualifying_cheats(input, 20, 50);
+        assert_eq!(actual.len(), 285);
+        assert!(actual.iter().all(|c| c.saved >= 50));
+        assert!(actual.iter().all(|c| distance(&c.start, &c.end) <= 20));
+    }
+
+    #[test]
+    fn test_branching_track() {
+        // The end is in the middle of a dead end, so positions past it take longer to reach
+        let input = "#######\n\
+                     #S#..E#\n\
+                     #.#.#.#\n\
+                     #...#.#\n\
+                     #######";
+
+        let track = Track::from(input);
+        assert_eq!(track.best, 8);
+        assert_eq!(track.from_start[&(5, 3)], 10);
+
+        assert_eq!(histogram(input, 2), BTreeMap::from([(2, 1), (4, 1)]));
+        assert_eq!(
+            qualifying_cheats(input, 2, 4),
+            vec![Cheat {
+                start: (1, 1),
+                end: (3, 1),
+                saved: 4
+            }]
+        );
+    }
 }
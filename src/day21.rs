@@ -1,9 +1,12 @@
-use std::{collections::HashMap, time::Instant};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Instant,
+};
 
 pub fn run_outer() -> String {
     let input = include_str!("../inputs/21.in");
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let (pt1, pt2) = run(input);
     let elapsed = Instant::now() - start;
     format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
@@ -13,247 +16,373 @@ pub fn run_outer() -> String {
     )
 }
 
-fn run(input: &str) -> (u64, u64) {
-    let codes: Vec<(u64, Vec<char>)> = input
-        .trim()
-        .lines()
-        .map(|line| (line[0..3].parse().unwrap(), line.chars().collect()))
-        .collect();
+/// Marks a position in a keypad layout with no button on it. The robot arms must never point here.
+const GAP: char = '#';
+
+/// The numeric keypad on the door:
+///
+///   7 8 9
+///   4 5 6
+///   1 2 3
+///     0 A
+const NUMERIC_KEYPAD: &str = "789\n456\n123\n#0A";
+
+/// The directional keypad used to control each robot:
+///
+///   ^ A
+/// < v >
+const DIRECTIONAL_KEYPAD: &str = "#^A\n<v>";
+
+const ALL_MOVES: [(char, (isize, isize)); 4] =
+    [('<', (-1, 0)), ('v', (0, 1)), ('^', (0, -1)), ('>', (1, 0))];
+
+struct Keypad {
+    layout: Vec<Vec<Option<char>>>,
+    positions: HashMap<char, (isize, isize)>,
+    /// All of the shortest gap-free ways of getting from one key to another and pressing it, keyed
+    /// on (start, end).
+    moves: HashMap<(char, char), Vec<Vec<char>>>,
+}
 
-    let mut pt1 = 0;
-    let mut pt2 = 0;
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum KeypadError {
+    /// There is no gap-free route between these two keys
+    Disconnected(char, char),
+    /// A key needed to operate the chain of keypads is missing
+    MissingKey(char),
+}
 
-    let mut cache = HashMap::new();
+impl TryFrom<&str> for Keypad {
+    type Error = KeypadError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let layout: Vec<Vec<Option<char>>> = s
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|c| if c == GAP { None } else { Some(c) })
+                    .collect()
+            })
+            .collect();
+
+        let mut positions = HashMap::new();
+        for (y, row) in layout.iter().enumerate() {
+            for (x, key) in row.iter().enumerate() {
+                if let Some(key) = key {
+                    positions.insert(*key, (x as isize, y as isize));
+                }
+            }
+        }
 
-    for (n, chars) in codes {
-        pt1 += run_one_sequence(n, &chars, 2, &mut cache);
-        pt2 += run_one_sequence(n, &chars, 25, &mut cache);
-    }
+        let mut keypad = Keypad {
+            layout,
+            positions,
+            moves: HashMap::new(),
+        };
+
+        // Every key must be reachable from every other, or there would be no shortest paths
+        // between them
+        let mut keys: Vec<char> = keypad.positions.keys().copied().collect();
+        keys.sort();
+        if let Some(first) = keys.first() {
+            let reachable = keypad.reachable(*first);
+            if let Some(key) = keys.iter().find(|k| !reachable.contains_key(k)) {
+                return Err(KeypadError::Disconnected(*first, *key));
+            }
+        }
 
-    (pt1, pt2)
-}
+        for start in keys.iter() {
+            for end in keys.iter() {
+                let paths = keypad.shortest_paths(*start, *end);
+                keypad.moves.insert((*start, *end), paths);
+            }
+        }
 
-fn run_one_sequence(
-    n: u64,
-    chars: &Vec<char>,
-    directional_keypads: u32,
-    cache: &mut HashMap<(Vec<char>, u32), u64>,
-) -> u64 {
-    let seq = seq_numeric(&chars);
-    let l = directional_recurse(seq, directional_keypads, cache);
-    l * n
+        Ok(keypad)
+    }
 }
 
-fn numeric_pos(c: char) -> (isize, isize) {
-    match c {
-        '7' => (0, 0),
-        '8' => (1, 0),
-        '9' => (2, 0),
-        '4' => (0, 1),
-        '5' => (1, 1),
-        '6' => (2, 1),
-        '1' => (0, 2),
-        '2' => (1, 2),
-        '3' => (2, 2),
-        '0' => (1, 3),
-        'A' => (2, 3),
-        _ => panic!("invalid character"),
+impl Keypad {
+    /// BFS distance of every position reachable from `origin` without crossing a gap.
+    fn distances(&self, origin: (isize, isize)) -> HashMap<(isize, isize), usize> {
+        let mut dist: HashMap<(isize, isize), usize> = HashMap::from([(origin, 0)]);
+        let mut queue = VecDeque::from([origin]);
+
+        while let Some(pos) = queue.pop_front() {
+            let d = dist[&pos];
+            for (_, delta) in ALL_MOVES.iter() {
+                let next = (pos.0 + delta.0, pos.1 + delta.1);
+                if self.key_at(next).is_some() && !dist.contains_key(&next) {
+                    dist.insert(next, d + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        dist
     }
-}
 
-fn directional_pos(c: char) -> (isize, isize) {
-    match c {
-        '^' => (1, 0),
-        'A' => (2, 0),
-        '<' => (0, 1),
-        'v' => (1, 1),
-        '>' => (2, 1),
-        _ => panic!("invalid character"),
+    /// Every key which can be reached from `key`, with its distance.
+    fn reachable(&self, key: char) -> HashMap<char, usize> {
+        self.distances(self.pos(key).unwrap())
+            .into_iter()
+            .map(|(pos, d)| (self.key_at(pos).unwrap(), d))
+            .collect()
     }
-}
 
-fn move_numeric(start: char, end: char) -> Vec<char> {
-    let start_pos = numeric_pos(start);
-    let end_pos = numeric_pos(end);
-    let mut out = Vec::new();
+    fn pos(&self, c: char) -> Option<(isize, isize)> {
+        self.positions.get(&c).copied()
+    }
 
-    // It's always best to do either:
-    // - all of the horizontal moves, then all of the vertical moves, or
-    // - all of the vertical moves, then all of the horizontal moves
-    // to maximise repeated button presses.
-    //
-    // If possible (ie without going over the blank square), we should prioritise moves in the
-    // following order: < v ^ >
-    //
-    // The numeric keypad looks like this:
-    //
-    //   7 8 9
-    //   4 5 6
-    //   1 2 3
-    //     0 A
-
-    if start_pos.1 == 3 && end_pos.0 == 0 {
-        // We have to go up first to avoid the blank square
-        for _ in 0..(start_pos.1 - end_pos.1) {
-            out.push('^');
+    fn key_at(&self, pos: (isize, isize)) -> Option<char> {
+        if pos.0 < 0 || pos.1 < 0 {
+            return None;
         }
 
-        for _ in 0..(start_pos.0 - end_pos.0) {
-            out.push('<');
-        }
-    } else if start_pos.0 == 0 && end_pos.1 == 3 {
-        // We have to go right first to avoid the blank square
-        for _ in 0..(end_pos.0 - start_pos.0) {
-            out.push('>');
-        }
+        *self.layout.get(pos.1 as usize)?.get(pos.0 as usize)?
+    }
 
-        for _ in 0..(end_pos.1 - start_pos.1) {
-            out.push('v');
-        }
-    } else {
-        // We are free to move in any direction first
-        if start_pos.0 > end_pos.0 {
-            for _ in 0..(start_pos.0 - end_pos.0) {
-                out.push('<');
+    /// Find every shortest sequence of presses which moves the arm from `start` to `end` without
+    /// crossing a gap, followed by an `A` to press the key.
+    ///
+    /// Distances to `end` are found with a BFS over the buttons, then every path which always
+    /// steps one closer is enumerated. This copes with any number of gaps, including layouts where
+    /// the shortest route is not a simple L shape.
+    fn shortest_paths(&self, start: char, end: char) -> Vec<Vec<char>> {
+        let start_pos = self.pos(start).unwrap();
+        let end_pos = self.pos(end).unwrap();
+        let dist = self.distances(end_pos);
+
+        let mut paths = Vec::new();
+        let mut stack = vec![(start_pos, Vec::new())];
+
+        while let Some((pos, path)) = stack.pop() {
+            if pos == end_pos {
+                let mut path = path;
+                path.push('A');
+                paths.push(path);
+                continue;
             }
-        }
 
-        if start_pos.1 < end_pos.1 {
-            for _ in 0..(end_pos.1 - start_pos.1) {
-                out.push('v');
+            let d = dist[&pos];
+            for (c, delta) in ALL_MOVES.iter().rev() {
+                let next = (pos.0 + delta.0, pos.1 + delta.1);
+                if dist.get(&next) == Some(&(d - 1)) {
+                    let mut next_path = path.clone();
+                    next_path.push(*c);
+                    stack.push((next, next_path));
+                }
             }
         }
 
-        if start_pos.1 > end_pos.1 {
-            for _ in 0..(start_pos.1 - end_pos.1) {
-                out.push('^');
-            }
-        }
+        paths
+    }
 
-        if start_pos.0 < end_pos.0 {
-            for _ in 0..(end_pos.0 - start_pos.0) {
-                out.push('>');
-            }
-        }
+    fn paths(&self, start: char, end: char) -> &[Vec<char>] {
+        self.moves
+            .get(&(start, end))
+            .unwrap_or_else(|| panic!("invalid move {start} -> {end}"))
     }
+}
 
-    out.push('A');
-    out
+/// A numeric keypad operated through a chain of robots, each controlled from a directional keypad.
+struct KeypadChain {
+    numeric: Keypad,
+    directional: Keypad,
+    cache: HashMap<(Vec<char>, u32), u64>,
 }
 
-fn move_directional(start: char, end: char) -> Vec<char> {
-    let start_pos = directional_pos(start);
-    let end_pos = directional_pos(end);
-    let mut out = Vec::new();
+impl KeypadChain {
+    fn new(numeric: &str, directional: &str) -> Result<Self, KeypadError> {
+        let numeric = Keypad::try_from(numeric)?;
+        let directional = Keypad::try_from(directional)?;
 
-    // It's always best to do either:
-    // - all of the horizontal moves, then all of the vertical moves, or
-    // - all of the vertical moves, then all of the horizontal moves
-    // to maximise repeated button presses.
-    //
-    // If possible (ie without going over the blank square), we should prioritise moves in the
-    // following order: < ^ v >
-    //
-    // The directional keypad looks like this:
-    //
-    //   ^ A
-    // < v >
-
-    if start_pos.0 == 0 && end_pos.1 == 0 {
-        // We have to go right first to avoid the blank square
-        for _ in 0..(end_pos.0 - start_pos.0) {
-            out.push('>');
+        // Both keypads start on `A`, and the directional keypad needs a key for every move
+        if numeric.pos('A').is_none() {
+            return Err(KeypadError::MissingKey('A'));
+        }
+        let mut needed = ALL_MOVES.iter().map(|(c, _)| *c).chain(['A']);
+        if let Some(key) = needed.find(|c| directional.pos(*c).is_none()) {
+            return Err(KeypadError::MissingKey(key));
         }
 
-        for _ in 0..(start_pos.1 - end_pos.1) {
-            out.push('^');
+        Ok(Self {
+            numeric,
+            directional,
+            cache: HashMap::new(),
+        })
+    }
+
+    /// Number of presses on the outermost keypad needed to type `code` on the numeric keypad.
+    fn sequence_len(&mut self, code: &[char], directional_keypads: u32) -> u64 {
+        let mut current = 'A';
+        let mut res = 0;
+
+        for c in code.iter() {
+            let paths = self.numeric.paths(current, *c).to_vec();
+            res += paths
+                .into_iter()
+                .map(|path| self.directional_recurse(path, directional_keypads))
+                .min()
+                .unwrap();
+            current = *c;
         }
-    } else if start_pos.1 == 0 && end_pos.0 == 0 {
-        // We have to go down first to avoid the blank square
-        for _ in 0..(end_pos.1 - start_pos.1) {
-            out.push('v');
+
+        res
+    }
+
+    /// Number of presses on the outermost keypad needed to type `ip` on a directional keypad which
+    /// is `depth` keypads further in.
+    fn directional_recurse(&mut self, ip: Vec<char>, depth: u32) -> u64 {
+        if depth == 0 {
+            return ip.len() as u64;
         }
 
-        for _ in 0..(start_pos.0 - end_pos.0) {
-            out.push('<');
+        if let Some(res) = self.cache.get(&(ip.clone(), depth)) {
+            return *res;
         }
-    } else {
-        // We are free to move in any direction first
-        if start_pos.0 > end_pos.0 {
-            for _ in 0..(start_pos.0 - end_pos.0) {
-                out.push('<');
-            }
+
+        let mut current = 'A';
+        let mut res = 0;
+
+        for c in ip.iter() {
+            let paths = self.directional.paths(current, *c).to_vec();
+            res += paths
+                .into_iter()
+                .map(|path| self.directional_recurse(path, depth - 1))
+                .min()
+                .unwrap();
+            current = *c;
         }
 
-        if start_pos.1 < end_pos.1 {
-            for _ in 0..(end_pos.1 - start_pos.1) {
-                out.push('v');
+        self.cache.insert((ip, depth), res);
+
+        res
+    }
+
+    /// The best way of pressing `end` on a keypad whose arm is at `start`, where the presses will
+    /// be made through `depth` further directional keypads.
+    fn best_move(&mut self, numeric: bool, start: char, end: char, depth: u32) -> Vec<char> {
+        let keypad = if numeric {
+            &self.numeric
+        } else {
+            &self.directional
+        };
+
+        let mut best: Option<(u64, Vec<char>)> = None;
+        for path in keypad.paths(start, end).to_vec() {
+            let len = self.directional_recurse(path.clone(), depth);
+            if best.as_ref().is_none_or(|(best_len, _)| len < *best_len) {
+                best = Some((len, path));
             }
         }
 
-        if start_pos.1 > end_pos.1 {
-            for _ in 0..(start_pos.1 - end_pos.1) {
-                out.push('^');
-            }
+        best.unwrap().1
+    }
+
+    fn expand(&mut self, ip: &[char], numeric: bool, depth: u32) -> Vec<char> {
+        let mut current = 'A';
+        let mut out = Vec::new();
+
+        for c in ip.iter() {
+            let next_seq = self.best_move(numeric, current, *c, depth);
+            out.extend_from_slice(&next_seq);
+            current = *c;
         }
 
-        if start_pos.0 < end_pos.0 {
-            for _ in 0..(end_pos.0 - start_pos.0) {
-                out.push('>');
-            }
+        out
+    }
+
+    /// Build the concrete sequence of presses needed on the outermost keypad to type `code`, with
+    /// `directional_keypads` robot-operated directional keypads between us and the numeric keypad.
+    ///
+    /// The output grows exponentially with depth, so this is only practical for small depths. Use
+    /// `sequence_len` when only the length is needed.
+    #[allow(unused)]
+    fn press_sequence(&mut self, code: &[char], directional_keypads: u32) -> Vec<char> {
+        let mut seq = self.expand(code, true, directional_keypads);
+
+        for depth in (0..directional_keypads).rev() {
+            seq = self.expand(&seq, false, depth);
         }
+
+        seq
     }
 
-    out.push('A');
-    out
+    /// Replay presses made on the outermost keypad through a chain of `directional_keypads`
+    /// robots, returning the code typed on the numeric keypad. Returns `None` if any robot's arm
+    /// points at a gap along the way.
+    #[allow(unused)]
+    fn simulate(&self, presses: &[char], directional_keypads: u32) -> Option<Vec<char>> {
+        let mut seq = presses.to_vec();
+
+        for _ in 0..directional_keypads {
+            seq = replay(&seq, &self.directional)?;
+        }
+
+        replay(&seq, &self.numeric)
+    }
 }
 
-fn seq_numeric(ip: &[char]) -> Vec<char> {
-    let mut current = 'A';
+/// Replay presses on a single keypad, starting with the arm pointing at `A`, and return the keys
+/// that get pressed. Returns `None` if the arm ever points at a gap or off the keypad.
+fn replay(presses: &[char], keypad: &Keypad) -> Option<Vec<char>> {
+    let mut pos = keypad.pos('A')?;
     let mut out = Vec::new();
 
-    for c in ip.iter() {
-        let next_seq = move_numeric(current, *c);
-        out.extend_from_slice(&next_seq);
-        current = *c;
+    for c in presses.iter() {
+        if *c == 'A' {
+            out.push(keypad.key_at(pos)?);
+            continue;
+        }
+
+        let (_, delta) = ALL_MOVES.iter().find(|(m, _)| m == c)?;
+        pos = (pos.0 + delta.0, pos.1 + delta.1);
+        keypad.key_at(pos)?;
     }
 
-    out
+    Some(out)
 }
 
-fn directional_recurse(
-    ip: Vec<char>,
-    depth: u32,
-    cache: &mut HashMap<(Vec<char>, u32), u64>,
-) -> u64 {
-    if let Some(res) = cache.get(&(ip.clone(), depth)) {
-        return *res;
-    }
+fn run_inner(input: &str, numeric: &str, directional: &str) -> Result<(u64, u64), KeypadError> {
+    let codes: Vec<(u64, Vec<char>)> = input
+        .trim()
+        .lines()
+        .map(|line| (line[0..3].parse().unwrap(), line.chars().collect()))
+        .collect();
 
-    let mut current = 'A';
-    let mut res = 0;
+    let mut pt1 = 0;
+    let mut pt2 = 0;
 
-    for c in ip.iter() {
-        let seq = move_directional(current, *c);
+    let mut chain = KeypadChain::new(numeric, directional)?;
 
-        if depth == 1 {
-            res += seq.len() as u64;
-        } else {
-            res += directional_recurse(seq, depth - 1, cache);
+    // Every key in the codes has to be on the numeric keypad
+    for (_, chars) in codes.iter() {
+        if let Some(key) = chars.iter().find(|c| chain.numeric.pos(**c).is_none()) {
+            return Err(KeypadError::MissingKey(*key));
         }
+    }
 
-        current = *c;
+    for (n, chars) in codes {
+        pt1 += chain.sequence_len(&chars, 2) * n;
+        pt2 += chain.sequence_len(&chars, 25) * n;
     }
 
-    cache.insert((ip, depth), res);
+    Ok((pt1, pt2))
+}
 
-    res
+fn run(input: &str) -> (u64, u64) {
+    run_inner(input, NUMERIC_KEYPAD, DIRECTIONAL_KEYPAD).expect("Puzzle keypads should be valid")
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
     #[test]
     fn test_example() {
         let input = include_str!("../inputs/21.ex");
@@ -263,27 +392,135 @@ mod test {
 
     #[test]
     fn test_move_numeric() {
-        assert_eq!(move_numeric('7', '9'), vec!['>', '>', 'A']);
-        assert_eq!(move_numeric('6', '4'), vec!['<', '<', 'A']);
-        assert_eq!(move_numeric('1', '7'), vec!['^', '^', 'A']);
-        assert_eq!(move_numeric('8', '2'), vec!['v', 'v', 'A']);
-        assert_eq!(move_numeric('7', '0'), vec!['>', 'v', 'v', 'v', 'A']);
-        assert_eq!(move_numeric('A', '4'), vec!['^', '^', '<', '<', 'A']);
-        assert_eq!(move_numeric('1', '6'), vec!['^', '>', '>', 'A']);
+        let mut chain = KeypadChain::new(NUMERIC_KEYPAD, DIRECTIONAL_KEYPAD).unwrap();
+        assert_eq!(chain.best_move(true, '7', '9', 2), chars(">>A"));
+        assert_eq!(chain.best_move(true, '6', '4', 2), chars("<<A"));
+        assert_eq!(chain.best_move(true, '1', '7', 2), chars("^^A"));
+        assert_eq!(chain.best_move(true, '8', '2', 2), chars("vvA"));
+        assert_eq!(chain.best_move(true, '7', '0', 2), chars(">vvvA"));
+        assert_eq!(chain.best_move(true, 'A', '4', 2), chars("^^<<A"));
+        assert_eq!(chain.best_move(true, '1', '6', 2), chars("^>>A"));
     }
 
     #[test]
     fn test_move_directional() {
-        assert_eq!(move_directional('<', '>'), vec!['>', '>', 'A']);
-        assert_eq!(move_directional('^', 'v'), vec!['v', 'A']);
-        assert_eq!(move_directional('<', 'A'), vec!['>', '>', '^', 'A']);
-        assert_eq!(move_directional('A', '<'), vec!['v', '<', '<', 'A']);
+        let mut chain = KeypadChain::new(NUMERIC_KEYPAD, DIRECTIONAL_KEYPAD).unwrap();
+        assert_eq!(chain.best_move(false, '<', '>', 2), chars(">>A"));
+        assert_eq!(chain.best_move(false, '^', 'v', 2), chars("vA"));
+        assert_eq!(chain.best_move(false, '<', 'A', 2), chars(">>^A"));
+        assert_eq!(chain.best_move(false, 'A', '<', 2), chars("v<<A"));
+    }
+
+    #[test]
+    fn test_shortest_paths() {
+        let keypad = Keypad::try_from(NUMERIC_KEYPAD).unwrap();
+        assert_eq!(keypad.paths('7', '9'), &[chars(">>A")]);
+
+        let mut paths = keypad.paths('A', '1').to_vec();
+        paths.sort();
+        assert_eq!(paths, vec![chars("<^<A"), chars("^<<A")]);
+
+        // Gaps down both sides force a detour through the centre
+        let keypad = Keypad::try_from("123\n#4#\n#5#\n678").unwrap();
+        assert_eq!(keypad.paths('1', '6'), &[chars(">vvv<A")]);
+    }
+
+    #[test]
+    fn test_sequence_len() {
+        let mut chain = KeypadChain::new(NUMERIC_KEYPAD, DIRECTIONAL_KEYPAD).unwrap();
+        assert_eq!(chain.sequence_len(&chars("029A"), 0), 12);
+        assert_eq!(chain.sequence_len(&chars("029A"), 1), 28);
+        assert_eq!(chain.sequence_len(&chars("029A"), 2), 68);
+        assert_eq!(chain.sequence_len(&chars("980A"), 2), 60);
+        assert_eq!(chain.sequence_len(&chars("179A"), 2), 68);
+        assert_eq!(chain.sequence_len(&chars("456A"), 2), 64);
+        assert_eq!(chain.sequence_len(&chars("379A"), 2), 64);
+    }
+
+    #[test]
+    fn test_press_sequence() {
+        let mut chain = KeypadChain::new(NUMERIC_KEYPAD, DIRECTIONAL_KEYPAD).unwrap();
+        let code = chars("029A");
+        assert_eq!(chain.press_sequence(&code, 0).len(), "<A^A>^^AvvvA".len());
+        assert_eq!(
+            chain.press_sequence(&code, 1).len(),
+            "v<<A>>^A<A>AvA<^AA>A<vAAA>^A".len()
+        );
+        assert_eq!(chain.press_sequence(&code, 2).len(), 68);
+
+        for depth in 1..5 {
+            let len = chain.sequence_len(&code, depth);
+            assert_eq!(chain.press_sequence(&code, depth).len() as u64, len);
+        }
+    }
+
+    #[test]
+    fn test_simulate() {
+        let mut chain = KeypadChain::new(NUMERIC_KEYPAD, DIRECTIONAL_KEYPAD).unwrap();
+        for code in ["029A", "980A", "179A", "456A", "379A"] {
+            let code = chars(code);
+            for depth in 0..4 {
+                let presses = chain.press_sequence(&code, depth);
+                assert_eq!(chain.simulate(&presses, depth), Some(code.clone()));
+            }
+        }
+
+        assert_eq!(
+            chain.simulate(&chars("<A^A>^^AvvvA"), 0),
+            Some(chars("029A"))
+        );
+
+        // Moving left from 0 points the arm at the gap
+        assert_eq!(chain.simulate(&chars("<<A"), 0), None);
+        assert_eq!(chain.simulate(&chars("<<A"), 1), None);
+    }
+
+    #[test]
+    fn test_custom_keypads() {
+        // A numeric keypad with the gap in the top left corner
+        let numeric = "#0A\n123\n456\n789";
+        let mut chain = KeypadChain::new(numeric, DIRECTIONAL_KEYPAD).unwrap();
+        for code in ["029A", "980A", "179A", "456A", "379A"] {
+            let code = chars(code);
+            let presses = chain.press_sequence(&code, 2);
+            assert_eq!(presses.len() as u64, chain.sequence_len(&code, 2));
+            assert_eq!(chain.simulate(&presses, 2), Some(code.clone()));
+        }
+
+        // A numeric keypad with a second gap where the 8 used to be
+        let numeric = "7#9\n456\n123\n#0A";
+        let mut chain = KeypadChain::new(numeric, DIRECTIONAL_KEYPAD).unwrap();
+        for code in ["029A", "179A", "456A", "379A"] {
+            let code = chars(code);
+            let presses = chain.press_sequence(&code, 2);
+            assert_eq!(presses.len() as u64, chain.sequence_len(&code, 2));
+            assert_eq!(chain.simulate(&presses, 2), Some(code.clone()));
+        }
     }
 
     #[test]
-    fn test_seq_numeric() {
-        let input: Vec<char> = "029A".chars().collect();
-        let seq = seq_numeric(&input);
-        assert_eq!(seq.len(), "<A^A>^^AvvvA".len());
+    fn test_invalid_keypads() {
+        // The 3 is cut off from the other keys by gaps
+        assert_eq!(
+            Keypad::try_from("12#\n##3").err(),
+            Some(KeypadError::Disconnected('1', '3'))
+        );
+        assert!(KeypadChain::new("12#\n##3\n##A", DIRECTIONAL_KEYPAD).is_err());
+
+        // Both keypads need an A, and the directional keypad needs every move
+        assert_eq!(
+            KeypadChain::new("789\n456\n123", DIRECTIONAL_KEYPAD).err(),
+            Some(KeypadError::MissingKey('A'))
+        );
+        assert_eq!(
+            KeypadChain::new(NUMERIC_KEYPAD, "#^A\n<v#").err(),
+            Some(KeypadError::MissingKey('>'))
+        );
+
+        // The codes can only use keys on the numeric keypad
+        assert_eq!(
+            run_inner("029A\n", "789\n456\n12A", DIRECTIONAL_KEYPAD).err(),
+            Some(KeypadError::MissingKey('0'))
+        );
     }
 }
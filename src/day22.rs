@@ -1,88 +1,333 @@
-use std::{collections::HashMap, time::Instant};
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::{
+    fmt::{Display, Write},
+    time::Instant,
+};
 
 const PRUNE_MOD: u32 = 16777216;
 
 pub fn run_outer() -> String {
+    run_outer_with(CYCLES, WINDOW_LEN, false)
+}
+
+/// Run with a different number of secret numbers per buyer or length of price change sequence to
+/// the puzzle, and optionally list the price each buyer sells at for the best sequence.
+pub fn run_outer_with(cycles: usize, window_len: u32, show_prices: bool) -> String {
     let input = include_str!("../inputs/22.in");
+    let params = match Params::new(cycles, window_len) {
+        Ok(params) => params,
+        Err(e) => return e.to_string(),
+    };
+
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let (pt1, pt2) = run_inner(input, params);
     let elapsed = Instant::now() - start;
-    format!(
-        "pt1: {} , pt2: {} , elapsed time {:?} us",
+    let mut out = format!(
+        "pt1: {} , pt2: {} ({}) , elapsed time {:?} us",
         pt1,
-        pt2,
+        pt2.bananas,
+        pt2.diffs_str(),
         elapsed.as_micros()
-    )
+    );
+
+    if show_prices {
+        let numbers = parse(input);
+        for (n, price) in numbers.iter().zip(pt2.prices(&numbers, params)) {
+            match price {
+                Some(price) => write!(out, "\n{n}: {price}").unwrap(),
+                None => write!(out, "\n{n}: -").unwrap(),
+            }
+        }
+    }
+
+    out
 }
 
-fn run(input: &str) -> (u64, u64) {
-    let numbers: Vec<u32> = input
-        .trim()
-        .lines()
-        .map(|line| line.parse().unwrap())
+/// Set of lanes, one bit per lane
+type LaneMask = u8;
+/// Number of buyers evolved together. The steps are the same for every buyer, so working on a
+/// fixed-size array of them lets the compiler vectorise each step.
+const LANES: usize = LaneMask::BITS as usize;
+/// Number of new secret numbers each buyer generates in the puzzle
+pub const CYCLES: usize = 2000;
+/// Number of consecutive price changes the monkey looks for in the puzzle
+pub const WINDOW_LEN: u32 = 4;
+/// Longest window of price changes which can be searched for
+const MAX_WINDOW_LEN: u32 = 5;
+/// Each price change is between -9 and +9, so is one of 19 values
+const DIFF_VALUES: usize = 19;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Params {
+    /// Number of new secret numbers each buyer generates
+    cycles: usize,
+    /// Number of consecutive price changes the monkey looks for. The search needs a 4 byte total
+    /// and an 8 byte seen marker for every possible window, and each rayon fold has its own copy,
+    /// so this is capped at `MAX_WINDOW_LEN`: 19^5 slots is around 30MB per fold, but 19^6 would
+    /// already be around 560MB.
+    window_len: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ParamsError {
+    /// The window is empty or longer than `MAX_WINDOW_LEN`
+    WindowLen(u32),
+    /// Not enough secret numbers for a buyer to see a whole window
+    TooFewCycles { cycles: usize, window_len: u32 },
+}
+
+impl Display for ParamsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamsError::WindowLen(len) => write!(
+                f,
+                "window length {len} is not supported, it must be from 1 to {MAX_WINDOW_LEN}"
+            ),
+            ParamsError::TooFewCycles { cycles, window_len } => write!(
+                f,
+                "{cycles} secret numbers is too few to see {window_len} price changes, it needs \
+                 more than {window_len}"
+            ),
+        }
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            cycles: CYCLES,
+            window_len: WINDOW_LEN,
+        }
+    }
+}
+
+impl Params {
+    /// Check that the window can be searched for, and that each buyer generates enough secret
+    /// numbers to see at least one whole window. The first secret number has no price change.
+    fn new(cycles: usize, window_len: u32) -> Result<Self, ParamsError> {
+        if !(1..=MAX_WINDOW_LEN).contains(&window_len) {
+            return Err(ParamsError::WindowLen(window_len));
+        }
+        if cycles <= window_len as usize {
+            return Err(ParamsError::TooFewCycles { cycles, window_len });
+        }
+
+        Ok(Self { cycles, window_len })
+    }
+
+    /// Number of distinct windows of price changes
+    fn window_slots(&self) -> usize {
+        DIFF_VALUES.pow(self.window_len)
+    }
+}
+
+fn evolve(val: u32) -> u32 {
+    let val = (val ^ (val << 6)) % PRUNE_MOD;
+    let val = (val ^ (val >> 5)) % PRUNE_MOD;
+    (val ^ (val << 11)) % PRUNE_MOD
+}
+
+/// Advance the secret number of every lane by one step.
+fn evolve_lanes(vals: &mut [u32; LANES]) {
+    for val in vals.iter_mut() {
+        *val = evolve(*val);
+    }
+}
+
+/// Each complete window of price changes seen by a single buyer, with the price at the end of it.
+/// Windows are encoded as a base 19 number, with the most recent change as the least significant
+/// digit, so taking the remainder drops the oldest one.
+fn buyer_windows(number: u32, params: Params) -> impl Iterator<Item = (usize, u32)> {
+    let slots = params.window_slots();
+    let mut val = number;
+    let mut prev_price = 0;
+    let mut window = 0;
+
+    (0..params.cycles).filter_map(move |cycle| {
+        val = evolve(val);
+        let price = val % 10;
+        let diff = (price + 9 - prev_price) as usize;
+        prev_price = price;
+
+        // Price changes are only counted from the first secret number generated
+        if cycle == 0 {
+            return None;
+        }
+
+        window = (window * DIFF_VALUES + diff) % slots;
+        (cycle >= params.window_len as usize).then_some((window, price))
+    })
+}
+
+/// Decode a window of price changes, oldest first.
+fn decode_window(mut window: usize, window_len: u32) -> Vec<i32> {
+    let mut diffs: Vec<i32> = (0..window_len)
+        .map(|_| {
+            let diff = (window % DIFF_VALUES) as i32 - 9;
+            window /= DIFF_VALUES;
+            diff
+        })
         .collect();
+    diffs.reverse();
+    diffs
+}
 
-    let sequences: Vec<Vec<u32>> = numbers.iter().map(|n| evolve(*n, 2000)).collect();
+/// Running totals for the search over buyers.
+struct PriceSearch {
+    /// Sum of the final secret number of each buyer
+    secret_sum: u64,
+    /// Total bananas bought for each window of price changes, indexed by its encoding
+    totals: Vec<u32>,
+    /// The last set of buyers to see each window, and which lanes of that set have seen it, so that
+    /// only the first time each buyer sees a window is counted
+    seen: Vec<(u32, LaneMask)>,
+    /// Identifier of the next set of buyers, starting from 1 so that 0 in `seen` means never seen
+    next_chunk: u32,
+    params: Params,
+}
 
-    let pt1 = sequences
-        .iter()
-        .map(|seq| *seq.last().unwrap() as u64)
-        .sum();
-
-    // The HashMap key is effectively a sequence of four diffs, for which each diff must be between
-    // -9 to +9. But storing this in a Vec is not very efficient, so instead we can encode it by
-    // adding 9 to each diff (to make sure it is non-negative), and storing the result in a u32 with
-    // each consecutive value left-shifted by 8 bits relative to the last one (we could actually left
-    // shift by less than 8 bits but 8 makes things easier to debug since each diff is in its own set
-    // of two bytes).
-    let mut overall_hm: HashMap<u32, u32> = HashMap::new();
-
-    for seq in sequences {
-        let mut hm: HashMap<u32, u32> = HashMap::new();
-
-        for w in seq.windows(5) {
-            let price = w[4] % 10;
-            let diffs: u32 = w
-                .windows(2)
-                .enumerate()
-                .map(|(i, a)| {
-                    let diff = (a[1] % 10) as i32 - (a[0] % 10) as i32;
-                    let diff_nonnegative = (diff + 9) as u32;
-                    diff_nonnegative << (8 * i)
-                })
-                .sum();
-
-            // Only the first occurrence of any sequence is relevant
-            if !hm.contains_key(&diffs) {
-                hm.insert(diffs.clone(), price);
-
-                // Update overall hashmap
-                let total_price = overall_hm.entry(diffs).or_insert(0);
-                *total_price += price;
+impl PriceSearch {
+    fn new(params: Params) -> Self {
+        let slots = params.window_slots();
+        Self {
+            secret_sum: 0,
+            totals: vec![0; slots],
+            seen: vec![(0, 0); slots],
+            next_chunk: 1,
+            params,
+        }
+    }
+
+    /// Evolve up to `LANES` buyers together, adding their prices to the totals.
+    fn add_buyers(&mut self, numbers: &[u32]) {
+        let mut vals = [0; LANES];
+        vals[..numbers.len()].copy_from_slice(numbers);
+
+        let chunk = self.next_chunk;
+        self.next_chunk += 1;
+
+        // This is the same as `buyer_windows`, but for every lane at once
+        let slots = self.totals.len();
+        let mut prices = [0; LANES];
+        let mut windows = [0; LANES];
+
+        for cycle in 0..self.params.cycles {
+            evolve_lanes(&mut vals);
+
+            for lane in 0..numbers.len() {
+                let price = vals[lane] % 10;
+                let diff = (price + 9 - prices[lane]) as usize;
+                prices[lane] = price;
+
+                // Price changes are only counted from the first secret number generated
+                if cycle == 0 {
+                    continue;
+                }
+
+                windows[lane] = (windows[lane] * DIFF_VALUES + diff) % slots;
+                if cycle < self.params.window_len as usize {
+                    continue;
+                }
+
+                let seen = &mut self.seen[windows[lane]];
+                if seen.0 != chunk {
+                    *seen = (chunk, 0);
+                }
+
+                let bit = 1 << lane;
+                if seen.1 & bit == 0 {
+                    seen.1 |= bit;
+                    self.totals[windows[lane]] += price;
+                }
             }
         }
+
+        self.secret_sum += vals[..numbers.len()].iter().map(|v| *v as u64).sum::<u64>();
     }
 
-    let pt2 = overall_hm.values().max().unwrap();
+    /// Combine the totals for two separate sets of buyers.
+    fn merge(mut self, other: Self) -> Self {
+        self.secret_sum += other.secret_sum;
+        for (total, other_total) in self.totals.iter_mut().zip(other.totals) {
+            *total += other_total;
+        }
+        self
+    }
+}
 
-    (pt1, *pt2 as u64)
+/// The window of price changes which gets the most bananas.
+#[derive(Debug, PartialEq, Eq)]
+struct BestSequence {
+    /// Encoding of the window, as used by the search
+    window: usize,
+    diffs: Vec<i32>,
+    bananas: u32,
 }
 
-fn evolve(n: u32, cycles: u32) -> Vec<u32> {
-    let mut val = n;
-    let mut res = Vec::with_capacity(cycles as usize);
+impl BestSequence {
+    /// The price changes separated by commas, as written in the puzzle.
+    fn diffs_str(&self) -> String {
+        self.diffs.iter().map(|d| d.to_string()).join(",")
+    }
 
-    for _ in 0..cycles {
-        val = val ^ (val << 6);
-        val = val.rem_euclid(PRUNE_MOD);
-        val = val ^ (val >> 5);
-        val = val.rem_euclid(PRUNE_MOD);
-        val = val ^ (val << 11);
-        val = val.rem_euclid(PRUNE_MOD);
-        res.push(val);
+    /// The price each buyer sells at, or `None` if they never see the sequence. Only the totals
+    /// are kept during the search, so this goes back through each buyer again.
+    fn prices(&self, numbers: &[u32], params: Params) -> Vec<Option<u32>> {
+        numbers
+            .par_iter()
+            .map(|n| {
+                buyer_windows(*n, params)
+                    .find(|(w, _)| *w == self.window)
+                    .map(|(_, price)| price)
+            })
+            .collect()
     }
+}
 
-    res
+fn parse(input: &str) -> Vec<u32> {
+    input
+        .trim()
+        .lines()
+        .map(|line| line.parse().unwrap())
+        .collect()
+}
+
+fn search(numbers: &[u32], params: Params) -> PriceSearch {
+    // Each thread works through its own share of the buyers with its own totals, then the totals
+    // are added together at the end
+    numbers
+        .par_chunks(LANES)
+        .fold(
+            || PriceSearch::new(params),
+            |mut search, chunk| {
+                search.add_buyers(chunk);
+                search
+            },
+        )
+        .reduce(|| PriceSearch::new(params), PriceSearch::merge)
+}
+
+fn best_sequence(search: &PriceSearch) -> BestSequence {
+    let (window, bananas) = search
+        .totals
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, total)| **total)
+        .unwrap();
+
+    BestSequence {
+        window,
+        diffs: decode_window(window, search.params.window_len),
+        bananas: *bananas,
+    }
+}
+
+fn run_inner(input: &str, params: Params) -> (u64, BestSequence) {
+    let numbers = parse(input);
+    let search = search(&numbers, params);
+
+    (search.secret_sum, best_sequence(&search))
 }
 
 #[cfg(test)]
@@ -92,14 +337,128 @@ mod test {
     #[test]
     fn test_example() {
         let input = include_str!("../inputs/22.ex");
-        let (pt1, _pt2) = run(&input);
+        let (pt1, _pt2) = run_inner(input, Params::default());
         assert_eq!(pt1, 37327623);
     }
 
     #[test]
     fn test_example_2() {
         let input = include_str!("../inputs/22_2.ex");
-        let (_pt1, pt2) = run(&input);
-        assert_eq!(pt2, 23);
+        let (_pt1, pt2) = run_inner(input, Params::default());
+        assert_eq!(pt2.bananas, 23);
+    }
+
+    #[test]
+    fn test_evolve_lanes() {
+        let mut vals = [123; LANES];
+        let expected = [
+            15887950, 16495136, 527345, 704524, 1553684, 12683156, 11100544, 12249484, 7753432,
+            5908254,
+        ];
+        for e in expected {
+            evolve_lanes(&mut vals);
+            assert_eq!(vals, [e; LANES]);
+        }
+    }
+
+    #[test]
+    fn test_partial_lanes() {
+        // Fewer buyers than lanes, and buyers split across more than one set of lanes
+        let numbers: Vec<u32> = (1..=LANES as u32 + 3).collect();
+
+        let params = Params::default();
+        let mut together = PriceSearch::new(params);
+        for chunk in numbers.chunks(LANES) {
+            together.add_buyers(chunk);
+        }
+
+        let separate = numbers
+            .iter()
+            .map(|n| {
+                let mut search = PriceSearch::new(params);
+                search.add_buyers(&[*n]);
+                search
+            })
+            .reduce(PriceSearch::merge)
+            .unwrap();
+
+        assert_eq!(together.secret_sum, separate.secret_sum);
+        assert!(together.totals == separate.totals);
+    }
+
+    #[test]
+    fn test_best_sequence() {
+        let input = include_str!("../inputs/22_2.ex");
+        let numbers = parse(input);
+        let params = Params::default();
+        let best = best_sequence(&search(&numbers, params));
+        assert_eq!(best.diffs_str(), "-2,1,-1,3");
+        assert_eq!(best.bananas, 23);
+        assert_eq!(
+            best.prices(&numbers, params),
+            vec![Some(7), Some(7), None, Some(9)]
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        let input = include_str!("../inputs/22_2.ex");
+        let numbers = parse(input);
+
+        for params in [
+            Params {
+                cycles: 2000,
+                window_len: 2,
+            },
+            Params {
+                cycles: 500,
+                window_len: 3,
+            },
+            Params {
+                cycles: 10,
+                window_len: 4,
+            },
+        ] {
+            let best = best_sequence(&search(&numbers, params));
+            assert_eq!(best.diffs.len(), params.window_len as usize);
+
+            // The prices each buyer sells at add up to the total from the search
+            let total: u32 = best.prices(&numbers, params).iter().flatten().sum();
+            assert_eq!(total, best.bananas);
+        }
+    }
+
+    #[test]
+    fn test_decode_window() {
+        let window = [-2, 1, -1, 3]
+            .iter()
+            .fold(0, |w, d| w * DIFF_VALUES + (d + 9) as usize);
+        assert_eq!(decode_window(window, 4), vec![-2, 1, -1, 3]);
+        assert_eq!(decode_window(0, 2), vec![-9, -9]);
+    }
+
+    #[test]
+    fn test_params_new() {
+        assert_eq!(Params::new(CYCLES, WINDOW_LEN), Ok(Params::default()));
+        assert_eq!(Params::default().window_slots(), 130321);
+        assert_eq!(Params::new(5, 4).unwrap().window_slots(), 130321);
+
+        // Windows which need too much memory, or can't be seen at all, are rejected
+        assert_eq!(Params::new(CYCLES, 6), Err(ParamsError::WindowLen(6)));
+        assert_eq!(
+            Params::new(CYCLES, u32::MAX),
+            Err(ParamsError::WindowLen(u32::MAX))
+        );
+        assert_eq!(Params::new(CYCLES, 0), Err(ParamsError::WindowLen(0)));
+        let too_few = |cycles| ParamsError::TooFewCycles {
+            cycles,
+            window_len: 4,
+        };
+        assert_eq!(Params::new(4, 4), Err(too_few(4)));
+        assert_eq!(Params::new(0, 4), Err(too_few(0)));
+
+        // With just enough secret numbers, each buyer sees a single window
+        let params = Params::new(5, 4).unwrap();
+        assert_eq!(buyer_windows(123, params).count(), 1);
     }
 }
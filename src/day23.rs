@@ -1,31 +1,63 @@
 use itertools::Itertools;
 use std::{
     collections::{HashMap, HashSet},
+    fmt::Write,
+    fs, io,
+    path::Path,
     time::Instant,
 };
 
+/// Computers whose names start with this are counted in part 1 of the puzzle
+pub const TRIANGLE_PREFIX: char = 't';
+
 pub fn run_outer() -> String {
+    run_outer_with(TRIANGLE_PREFIX, false, None)
+}
+
+/// Run with a different prefix for the triangles in part 1, and optionally list every maximal
+/// clique after the answers, or write the network to `dot_path` as a DOT graph.
+pub fn run_outer_with(prefix: char, list_cliques: bool, dot_path: Option<&Path>) -> String {
     let input = include_str!("../inputs/23.in");
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let graph = parse(input);
+    let cliques = maximal_cliques(&graph);
+    let (pt1, pt2) = answers(&graph, &cliques, prefix);
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
         pt1,
         pt2,
         elapsed.as_micros()
-    )
+    );
+
+    if list_cliques {
+        for clique in cliques.iter() {
+            write!(out, "\n{}: {}", clique.len(), clique.join(",")).unwrap();
+        }
+    }
+
+    if let Some(path) = dot_path {
+        match write_dot(&graph, &cliques, path) {
+            Ok(()) => write!(out, "\nwrote DOT graph to {}", path.display()).unwrap(),
+            Err(e) => write!(out, "\nfailed to write DOT graph: {e}").unwrap(),
+        }
+    }
+
+    out
 }
 
-fn run(input: &str) -> (u64, String) {
+fn parse(input: &str) -> HashMap<&str, HashSet<&str>> {
     let pairs: Vec<(&str, &str)> = input
         .trim()
         .lines()
         .map(|line| line.split_once('-').unwrap())
         .collect();
 
-    let graph = build_graph(pairs);
+    build_graph(pairs)
+}
 
+/// Count the sets of three inter-connected computers where at least one name starts with `prefix`.
+fn count_triangles(graph: &HashMap<&str, HashSet<&str>>, prefix: char) -> usize {
     let mut sets_of_three: HashSet<Vec<&str>> = HashSet::new();
     for (key, val) in graph.iter() {
         for combo in val.iter().combinations(2) {
@@ -33,10 +65,7 @@ fn run(input: &str) -> (u64, String) {
             let b = combo[1];
             if let Some(ga) = graph.get(a) {
                 if ga.contains(b) {
-                    let mut set = Vec::new();
-                    set.push(*key);
-                    set.push(a);
-                    set.push(b);
+                    let mut set = vec![*key, *a, *b];
                     set.sort(); // Must be sorted to avoid duplicates in HashSet
                     sets_of_three.insert(set);
                 }
@@ -44,13 +73,17 @@ fn run(input: &str) -> (u64, String) {
         }
     }
 
-    let pt1 = sets_of_three
+    sets_of_three
         .iter()
-        .filter(|s| s.iter().any(|elem| elem.starts_with('t')))
-        .count();
+        .filter(|s| s.iter().any(|elem| elem.starts_with(prefix)))
+        .count()
+}
 
+/// Every maximal clique, largest first. Cliques of the same size are
+/// in alphabetical order, and the computers within each clique are sorted.
+fn maximal_cliques<'a>(graph: &HashMap<&'a str, HashSet<&'a str>>) -> Vec<Vec<&'a str>> {
     let current_clique = HashSet::new();
-    let mut candidates = graph.keys().map(|k| *k).collect();
+    let mut candidates = graph.keys().copied().collect();
     let mut excluded = HashSet::new();
     let mut cliques = Vec::new();
 
@@ -58,17 +91,71 @@ fn run(input: &str) -> (u64, String) {
         &current_clique,
         &mut candidates,
         &mut excluded,
-        &graph,
+        graph,
         &mut cliques,
     );
 
-    let largest_clique = cliques
-        .iter_mut()
-        .max_by(|a, b| a.len().cmp(&b.len()))
-        .unwrap();
-    largest_clique.sort();
+    cliques.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    cliques
+}
+
+/// Render the network as a Graphviz DOT graph, with the computers and connections of
+/// `highlighted` drawn in red.
+fn to_dot(graph: &HashMap<&str, HashSet<&str>>, highlighted: &[&str]) -> String {
+    let mut computers: Vec<&str> = graph.keys().copied().collect();
+    computers.sort();
 
-    (pt1 as u64, format!("{}", largest_clique.join(",")))
+    let mut out = String::from("graph lan {\n");
+
+    for computer in computers.iter() {
+        let colour = if highlighted.contains(computer) {
+            " [color=red, fontcolor=red, penwidth=2]"
+        } else {
+            ""
+        };
+        writeln!(out, "    \"{computer}\"{colour};").unwrap();
+    }
+
+    // Each connection is stored in both directions, so only write it out from the first computer
+    for a in computers.iter() {
+        let mut neighbors: Vec<&str> = graph[a].iter().copied().filter(|b| a < b).collect();
+        neighbors.sort();
+        for b in neighbors {
+            let colour = if highlighted.contains(a) && highlighted.contains(&b) {
+                " [color=red, penwidth=2]"
+            } else {
+                ""
+            };
+            writeln!(out, "    \"{a}\" -- \"{b}\"{colour};").unwrap();
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Write the network as a DOT graph with the largest of `cliques` highlighted.
+fn write_dot(
+    graph: &HashMap<&str, HashSet<&str>>,
+    cliques: &[Vec<&str>],
+    path: &Path,
+) -> io::Result<()> {
+    let largest = cliques.first().map(|c| c.as_slice()).unwrap_or_default();
+    fs::write(path, to_dot(graph, largest))
+}
+
+/// The number of triangles with a computer starting with `prefix`, and the password made from
+/// the largest clique.
+fn answers(
+    graph: &HashMap<&str, HashSet<&str>>,
+    cliques: &[Vec<&str>],
+    prefix: char,
+) -> (u64, String) {
+    let pt1 = count_triangles(graph, prefix);
+
+    let largest_clique = cliques.first().unwrap();
+
+    (pt1 as u64, largest_clique.join(","))
 }
 
 fn build_graph<'a>(pairs: Vec<(&'a str, &'a str)>) -> HashMap<&'a str, HashSet<&'a str>> {
@@ -94,11 +181,9 @@ fn bron_kerbosch<'a>(
 ) {
     // Check recursion end condition
     if candidates.is_empty() && excluded.is_empty() {
-        if current_clique.len() > 2 {
-            let mut clique: Vec<&str> = current_clique.iter().cloned().collect();
-            clique.sort();
-            cliques.push(clique);
-        }
+        let mut clique: Vec<&str> = current_clique.iter().cloned().collect();
+        clique.sort();
+        cliques.push(clique);
     }
 
     // Select a pivot vertex that has the maximum number of neighbors
@@ -144,10 +229,15 @@ fn bron_kerbosch<'a>(
 mod test {
     use super::*;
 
+    fn run_inner(input: &str, prefix: char) -> (u64, String) {
+        let graph = parse(input);
+        answers(&graph, &maximal_cliques(&graph), prefix)
+    }
+
     #[test]
     fn test_example() {
         let input = include_str!("../inputs/23.ex");
-        let (pt1, pt2) = run(&input);
+        let (pt1, pt2) = run_inner(input, TRIANGLE_PREFIX);
         assert_eq!(pt1, 7);
         assert_eq!(pt2, "co,de,ka,ta");
     }
@@ -184,4 +274,79 @@ mod test {
 
         assert_eq!(cliques, vec![vec!["a", "b", "c"], vec!["d", "e", "f"]]);
     }
+
+    #[test]
+    fn test_prefix() {
+        let input = include_str!("../inputs/23.ex");
+        let graph = parse(input);
+        assert_eq!(count_triangles(&graph, 't'), 7);
+        assert_eq!(count_triangles(&graph, 'z'), 0);
+    }
+
+    #[test]
+    fn test_maximal_cliques() {
+        let input = include_str!("../inputs/23.ex");
+        let graph = parse(input);
+        let cliques = maximal_cliques(&graph);
+
+        assert_eq!(cliques[0], vec!["co", "de", "ka", "ta"]);
+        assert!(cliques.windows(2).all(|w| w[0].len() >= w[1].len()));
+
+        // Every clique is fully connected
+        for clique in cliques.iter() {
+            for (a, b) in clique.iter().tuple_combinations() {
+                assert!(graph[a].contains(b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_maximal_cliques_keeps_edges() {
+        // An isolated edge is a maximal clique of its own
+        let graph = build_graph(vec![("a", "b"), ("b", "c"), ("a", "c"), ("x", "y")]);
+        let cliques = maximal_cliques(&graph);
+        assert_eq!(cliques, vec![vec!["a", "b", "c"], vec!["x", "y"]]);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let graph = build_graph(vec![("a", "b"), ("b", "c"), ("a", "c"), ("c", "d")]);
+        let dot = to_dot(&graph, &["a", "b", "c"]);
+        assert_eq!(
+            dot,
+            "graph lan {\n    \
+             \"a\" [color=red, fontcolor=red, penwidth=2];\n    \
+             \"b\" [color=red, fontcolor=red, penwidth=2];\n    \
+             \"c\" [color=red, fontcolor=red, penwidth=2];\n    \
+             \"d\";\n    \
+             \"a\" -- \"b\" [color=red, penwidth=2];\n    \
+             \"a\" -- \"c\" [color=red, penwidth=2];\n    \
+             \"b\" -- \"c\" [color=red, penwidth=2];\n    \
+             \"c\" -- \"d\";\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_write_dot() {
+        let input = include_str!("../inputs/23.ex");
+        let dir = std::env::temp_dir().join("aoc2024_day23_dot");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let graph = parse(input);
+        let cliques = maximal_cliques(&graph);
+        let path = dir.join("lan.dot");
+        write_dot(&graph, &cliques, &path).unwrap();
+        let dot = fs::read_to_string(&path).unwrap();
+        assert_eq!(dot, to_dot(&graph, &["co", "de", "ka", "ta"]));
+        assert!(dot.contains("\"co\" -- \"de\" [color=red, penwidth=2];"));
+        assert!(dot.contains("    \"aq\";\n"));
+
+        // Writing somewhere that doesn't exist fails rather than panicking
+        let missing = dir.join("missing").join("lan.dot");
+        assert!(write_dot(&graph, &cliques, &missing).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
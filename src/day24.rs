@@ -1,19 +1,37 @@
 use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::time::Instant;
 
 use itertools::Itertools;
 
 pub fn run_outer() -> String {
+    run_outer_with(None)
+}
+
+/// Run the puzzle, and optionally write the circuit to `circuit_dir` as DOT and Verilog.
+pub fn run_outer_with(circuit_dir: Option<&Path>) -> String {
     let input = include_str!("../inputs/24.in");
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let (pt1, pt2) = run(input);
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
         pt1,
         pt2,
         elapsed.as_micros()
-    )
+    );
+
+    if let Some(dir) = circuit_dir {
+        match write_circuit(input, dir) {
+            Ok(()) => write!(out, "\nwrote circuit to {}", dir.display()).unwrap(),
+            Err(e) => write!(out, "\nfailed to write circuit: {e}").unwrap(),
+        }
+    }
+
+    out
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -43,10 +61,30 @@ struct Connection<'a> {
     output: &'a str,
 }
 
-fn run(input: &str) -> (u64, String) {
+impl Operation {
+    /// Graphviz fill colour used for gates of this type.
+    fn dot_colour(&self) -> &'static str {
+        match self {
+            Self::And => "lightblue",
+            Self::Or => "palegreen",
+            Self::Xor => "gold",
+        }
+    }
+
+    /// Verilog gate primitive implementing this operation.
+    fn verilog_primitive(&self) -> &'static str {
+        match self {
+            Self::And => "and",
+            Self::Or => "or",
+            Self::Xor => "xor",
+        }
+    }
+}
+
+fn parse(input: &str) -> (HashMap<&str, bool>, Vec<Connection<'_>>) {
     let (initial_values, connections) = input.trim().split_once("\n\n").unwrap();
 
-    let original_inputs: HashMap<&str, bool> = initial_values
+    let inputs: HashMap<&str, bool> = initial_values
         .lines()
         .map(|line| {
             let (name, val) = line.split_once(": ").unwrap();
@@ -55,14 +93,14 @@ fn run(input: &str) -> (u64, String) {
         })
         .collect();
 
-    let original_connections: Vec<Connection> = connections
+    let connections: Vec<Connection> = connections
         .lines()
         .map(|line| {
             let mut parts = line.split_whitespace();
             let a = parts.next().unwrap();
             let operation = Operation::try_from(parts.next().unwrap()).unwrap();
             let b = parts.next().unwrap();
-            let output = parts.skip(1).next().unwrap();
+            let output = parts.nth(1).unwrap();
             Connection {
                 inputs: (a, b),
                 operation,
@@ -71,6 +109,12 @@ fn run(input: &str) -> (u64, String) {
         })
         .collect();
 
+    (inputs, connections)
+}
+
+fn run(input: &str) -> (u64, String) {
+    let (original_inputs, original_connections) = parse(input);
+
     let mut inputs = original_inputs.clone();
     let mut connections = original_connections.clone();
 
@@ -120,6 +164,14 @@ fn run(input: &str) -> (u64, String) {
         }
     }
 
+    let wrong = find_wrong_wires(&original_connections);
+
+    (pt1, wrong.join(","))
+}
+
+/// Find the output wires which break the structure of a ripple carry adder, sorted and with
+/// duplicates removed.
+fn find_wrong_wires<'a>(original_connections: &[Connection<'a>]) -> Vec<&'a str> {
     let maxz = original_connections
         .iter()
         .filter_map(|val| extract_bit(val.output, 'z'))
@@ -158,14 +210,13 @@ fn run(input: &str) -> (u64, String) {
         }
 
         // 3.
-        if conn.operation == Operation::Xor {
-            if ![conn.output, conn.inputs.0, conn.inputs.1]
+        if conn.operation == Operation::Xor
+            && ![conn.output, conn.inputs.0, conn.inputs.1]
                 .iter()
                 .any(|val| val.starts_with('x') || val.starts_with('y') || val.starts_with('z'))
-            {
-                wrong.push(conn.output);
-                continue;
-            }
+        {
+            wrong.push(conn.output);
+            continue;
         }
 
         // 4.
@@ -203,8 +254,125 @@ fn run(input: &str) -> (u64, String) {
     }
 
     wrong.sort();
+    wrong.dedup();
+
+    wrong
+}
+
+/// Render the circuit as a Graphviz DOT graph.
+///
+/// Every wire is a node and every gate is a node coloured by its operation, so the graph can be
+/// laid out with `dot -Tsvg`. Any wires in `highlighted` (e.g. the output of `find_wrong_wires`)
+/// are drawn in red.
+fn to_dot(connections: &[Connection], highlighted: &[&str]) -> String {
+    let mut wires: Vec<&str> = connections
+        .iter()
+        .flat_map(|conn| [conn.inputs.0, conn.inputs.1, conn.output])
+        .collect();
+    wires.sort();
+    wires.dedup();
+
+    let mut out = String::from("digraph circuit {\n    rankdir=LR;\n");
+
+    for wire in wires {
+        let shape = match wire.chars().next() {
+            Some('x') | Some('y') | Some('z') => "box",
+            _ => "ellipse",
+        };
+        let colour = if highlighted.contains(&wire) {
+            ", color=red, fontcolor=red, penwidth=2"
+        } else {
+            ""
+        };
+        writeln!(out, "    \"{wire}\" [shape={shape}{colour}];").unwrap();
+    }
 
-    (pt1, wrong.iter().dedup().join(","))
+    for conn in connections {
+        let gate = format!("gate_{}", conn.output);
+        writeln!(
+            out,
+            "    \"{gate}\" [label=\"{:?}\", shape=diamond, style=filled, fillcolor={}];",
+            conn.operation,
+            conn.operation.dot_colour()
+        )
+        .unwrap();
+        writeln!(out, "    \"{}\" -> \"{gate}\";", conn.inputs.0).unwrap();
+        writeln!(out, "    \"{}\" -> \"{gate}\";", conn.inputs.1).unwrap();
+        writeln!(out, "    \"{gate}\" -> \"{}\";", conn.output).unwrap();
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Render the circuit as a structural Verilog module built from gate primitives.
+///
+/// Wires starting with `x` or `y` become module inputs, wires starting with `z` become module
+/// outputs and everything else is declared as an internal wire.
+fn to_verilog(connections: &[Connection], module_name: &str) -> String {
+    let mut inputs: Vec<&str> = connections
+        .iter()
+        .flat_map(|conn| [conn.inputs.0, conn.inputs.1])
+        .filter(|wire| wire.starts_with('x') || wire.starts_with('y'))
+        .collect();
+    inputs.sort();
+    inputs.dedup();
+
+    let mut outputs: Vec<&str> = connections
+        .iter()
+        .map(|conn| conn.output)
+        .filter(|wire| wire.starts_with('z'))
+        .collect();
+    outputs.sort();
+    outputs.dedup();
+
+    let mut internal: Vec<&str> = connections
+        .iter()
+        .map(|conn| conn.output)
+        .filter(|wire| !wire.starts_with('z'))
+        .collect();
+    internal.sort();
+    internal.dedup();
+
+    let ports = inputs
+        .iter()
+        .map(|wire| format!("    input wire {wire}"))
+        .chain(outputs.iter().map(|wire| format!("    output wire {wire}")))
+        .join(",\n");
+
+    let mut out = format!("module {module_name} (\n{ports}\n);\n");
+
+    for wire in internal {
+        writeln!(out, "    wire {wire};").unwrap();
+    }
+
+    out.push('\n');
+
+    for (i, conn) in connections.iter().enumerate() {
+        writeln!(
+            out,
+            "    {} g{i} ({}, {}, {});",
+            conn.operation.verilog_primitive(),
+            conn.output,
+            conn.inputs.0,
+            conn.inputs.1
+        )
+        .unwrap();
+    }
+
+    out.push_str("endmodule\n");
+    out
+}
+
+/// Write the circuit to `circuit.dot`, with the wires found by `find_wrong_wires` highlighted,
+/// and to `circuit.v` in `dir`.
+fn write_circuit(input: &str, dir: &Path) -> io::Result<()> {
+    let (_, connections) = parse(input);
+    let wrong = find_wrong_wires(&connections);
+
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join("circuit.dot"), to_dot(&connections, &wrong))?;
+    fs::write(dir.join("circuit.v"), to_verilog(&connections, "circuit"))
 }
 
 fn extract_bit(ip: &str, prefix: char) -> Option<u32> {
@@ -231,4 +399,52 @@ mod test {
         let (pt1, _pt2) = run(&input);
         assert_eq!(pt1, 2024);
     }
+
+    #[test]
+    fn test_to_dot() {
+        let input = include_str!("../inputs/24.ex");
+        let (_, connections) = parse(input);
+        let dot = to_dot(&connections, &["z01"]);
+        assert!(dot.starts_with("digraph circuit {"));
+        assert!(dot.contains(
+            "\"gate_z00\" [label=\"And\", shape=diamond, style=filled, fillcolor=lightblue];"
+        ));
+        assert!(dot.contains("\"x01\" -> \"gate_z01\";"));
+        assert!(dot.contains("\"gate_z02\" -> \"z02\";"));
+        assert!(dot.contains("\"z01\" [shape=box, color=red, fontcolor=red, penwidth=2];"));
+        assert!(dot.contains("\"z02\" [shape=box];"));
+    }
+
+    #[test]
+    fn test_to_verilog() {
+        let input = include_str!("../inputs/24.ex");
+        let (_, connections) = parse(input);
+        let verilog = to_verilog(&connections, "adder");
+        assert!(verilog.starts_with("module adder (\n    input wire x00,\n"));
+        assert!(verilog.contains("    output wire z02\n);\n"));
+        assert!(verilog.contains("    and g0 (z00, x00, y00);\n"));
+        assert!(verilog.contains("    xor g1 (z01, x01, y01);\n"));
+        assert!(verilog.contains("    or g2 (z02, x02, y02);\n"));
+        assert!(verilog.ends_with("endmodule\n"));
+    }
+
+    #[test]
+    fn test_write_circuit() {
+        let input = include_str!("../inputs/24.ex");
+        let dir = std::env::temp_dir().join("aoc2024_day24_write_circuit");
+        write_circuit(input, &dir).unwrap();
+
+        let (_, connections) = parse(input);
+        let wrong = find_wrong_wires(&connections);
+        assert_eq!(
+            fs::read_to_string(dir.join("circuit.dot")).unwrap(),
+            to_dot(&connections, &wrong)
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("circuit.v")).unwrap(),
+            to_verilog(&connections, "circuit")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
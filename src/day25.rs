@@ -1,65 +1,192 @@
 use itertools::Itertools;
+use std::fmt::Write;
 use std::time::Instant;
 
 pub fn run_outer() -> String {
+    run_outer_with(false)
+}
+
+/// Run the puzzle, optionally listing which keys fit each lock.
+pub fn run_outer_with(list_fits: bool) -> String {
     let input = include_str!("../inputs/25.in");
     let start = Instant::now();
-    let (pt1, pt2) = run(&input);
+    let (pt1, pt2) = run(input);
     let elapsed = Instant::now() - start;
-    format!(
+    let mut out = format!(
         "pt1: {} , pt2: {} , elapsed time {:?} us",
         pt1,
         pt2,
         elapsed.as_micros()
-    )
+    );
+
+    if list_fits {
+        out.push_str(&describe_fits(input).expect("Schematics should be valid"));
+    }
+
+    out
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Kind {
+    Lock,
+    Key,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum SchematicError {
+    /// Fewer than two rows, or no columns
+    TooSmall,
+    /// Rows of different lengths
+    Ragged,
+    /// Something other than `#` or `.`
+    BadCharacter,
+    /// Neither a full top row and empty bottom row (lock) nor the other way around (key)
+    NotLockOrKey,
+    /// A column which isn't a single run of pins from the top or bottom
+    BadColumn(usize),
+    /// Schematics of different sizes in the same input
+    SizeMismatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Schematic {
+    kind: Kind,
     heights: Vec<u32>,
+    /// Number of rows between the top and bottom rows, which is the most that a lock and key can
+    /// fill between them in any column
+    space: u32,
 }
 
-impl From<&str> for Schematic {
-    fn from(value: &str) -> Self {
-        let mut heights = vec![0; 5];
+impl TryFrom<&str> for Schematic {
+    type Error = SchematicError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let rows: Vec<&[u8]> = value.trim().lines().map(|l| l.as_bytes()).collect();
+        if rows.len() < 2 || rows[0].is_empty() {
+            return Err(SchematicError::TooSmall);
+        }
+
+        let width = rows[0].len();
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(SchematicError::Ragged);
+        }
+        if rows
+            .iter()
+            .flat_map(|row| row.iter())
+            .any(|c| !b"#.".contains(c))
+        {
+            return Err(SchematicError::BadCharacter);
+        }
+
+        let full = |row: &[u8]| row.iter().all(|c| *c == b'#');
+        let empty = |row: &[u8]| row.iter().all(|c| *c == b'.');
+        let (first, last) = (rows[0], rows[rows.len() - 1]);
+        let kind = if full(first) && empty(last) {
+            Kind::Lock
+        } else if empty(first) && full(last) {
+            Kind::Key
+        } else {
+            return Err(SchematicError::NotLockOrKey);
+        };
 
-        for line in value.trim().lines().skip(1).take(5) {
-            for (i, c) in line.chars().enumerate() {
-                if c == '#' {
-                    heights[i] += 1;
-                }
+        // Pins in locks hang down from the top, and in keys stand up from the bottom, so read
+        // each column from the pinned end and check that the pin is a single run
+        let mut heights = Vec::with_capacity(width);
+        for i in 0..width {
+            let column: Vec<u8> = match kind {
+                Kind::Lock => rows.iter().map(|row| row[i]).collect(),
+                Kind::Key => rows.iter().rev().map(|row| row[i]).collect(),
+            };
+            let pin = column.iter().take_while(|c| **c == b'#').count();
+            if column[pin..].contains(&b'#') {
+                return Err(SchematicError::BadColumn(i));
             }
+            heights.push(pin as u32 - 1);
         }
 
-        Schematic { heights }
+        Ok(Schematic {
+            kind,
+            heights,
+            space: rows.len() as u32 - 2,
+        })
     }
 }
 
-fn run(input: &str) -> (u64, u64) {
-    let mut keys: Vec<Schematic> = Vec::new();
+impl Schematic {
+    fn fits(&self, other: &Schematic) -> bool {
+        self.heights
+            .iter()
+            .zip(other.heights.iter())
+            .all(|(a, b)| a + b <= self.space)
+    }
+}
+
+/// Parse all locks and keys, checking that they are all the same size.
+fn parse(input: &str) -> Result<(Vec<Schematic>, Vec<Schematic>), SchematicError> {
     let mut locks: Vec<Schematic> = Vec::new();
+    let mut keys: Vec<Schematic> = Vec::new();
 
     for block in input.trim().split("\n\n") {
-        let sch = Schematic::from(block);
-        if block.starts_with('.') {
-            keys.push(sch);
-        } else {
-            locks.push(sch);
+        let sch = Schematic::try_from(block)?;
+
+        let first = locks.first().or(keys.first());
+        if first.is_some_and(|f| f.heights.len() != sch.heights.len() || f.space != sch.space) {
+            return Err(SchematicError::SizeMismatch);
         }
-    }
 
-    let mut pt1 = 0;
-    'outer: for (k, l) in keys.iter().cartesian_product(locks) {
-        for i in 0..5 {
-            if k.heights[i] + l.heights[i] > 5 {
-                continue 'outer;
-            }
+        match sch.kind {
+            Kind::Lock => locks.push(sch),
+            Kind::Key => keys.push(sch),
         }
+    }
 
-        pt1 += 1;
+    Ok((locks, keys))
+}
+
+/// For each lock, the indices of the keys which fit it.
+fn fitting_keys(locks: &[Schematic], keys: &[Schematic]) -> Vec<Vec<usize>> {
+    locks
+        .iter()
+        .map(|lock| {
+            keys.iter()
+                .enumerate()
+                .filter(|(_, key)| lock.fits(key))
+                .map(|(i, _)| i)
+                .collect()
+        })
+        .collect()
+}
+
+/// Describe which keys fit each lock, one line per lock, with both given by their pin heights.
+fn describe_fits(input: &str) -> Result<String, SchematicError> {
+    let (locks, keys) = parse(input)?;
+    let heights = |sch: &Schematic| sch.heights.iter().join(",");
+
+    let mut out = String::new();
+    for (lock, fits) in locks.iter().zip(fitting_keys(&locks, &keys)) {
+        let fits = fits.iter().map(|i| heights(&keys[*i])).join(" ");
+        write!(out, "\nlock {}: {}", heights(lock), fits).unwrap();
     }
+    Ok(out)
+}
+
+fn run(input: &str) -> (u64, u64) {
+    let (locks, keys) = parse(input).expect("Schematics should be valid");
 
-    (pt1, 0)
+    let pt1 = fitting_keys(&locks, &keys)
+        .iter()
+        .map(|fits| fits.len() as u64)
+        .sum();
+
+    // Number of distinct pin height profiles across all locks and keys
+    let pt2 = locks
+        .iter()
+        .chain(keys.iter())
+        .map(|sch| (sch.kind, &sch.heights))
+        .unique()
+        .count() as u64;
+
+    (pt1, pt2)
 }
 
 #[cfg(test)]
@@ -72,4 +199,67 @@ mod test {
         let (pt1, _pt2) = run(&input);
         assert_eq!(pt1, 3);
     }
+
+    #[test]
+    fn test_parse() {
+        let input = include_str!("../inputs/25.ex");
+        let (locks, keys) = parse(input).unwrap();
+        let heights = |v: &[Schematic]| v.iter().map(|s| s.heights.clone()).collect::<Vec<_>>();
+
+        assert_eq!(
+            heights(&locks),
+            vec![vec![0, 5, 3, 4, 3], vec![1, 2, 0, 5, 3]]
+        );
+        assert_eq!(
+            heights(&keys),
+            vec![
+                vec![5, 0, 2, 1, 3],
+                vec![4, 3, 4, 0, 2],
+                vec![3, 0, 2, 0, 1]
+            ]
+        );
+
+        let (_pt1, pt2) = run(input);
+        assert_eq!(pt2, 5);
+    }
+
+    #[test]
+    fn test_fitting_keys() {
+        let input = include_str!("../inputs/25.ex");
+        let (locks, keys) = parse(input).unwrap();
+        assert_eq!(fitting_keys(&locks, &keys), vec![vec![2], vec![1, 2]]);
+
+        assert_eq!(
+            describe_fits(input).unwrap(),
+            "\nlock 0,5,3,4,3: 3,0,2,0,1\nlock 1,2,0,5,3: 4,3,4,0,2 3,0,2,0,1"
+        );
+    }
+
+    #[test]
+    fn test_other_sizes() {
+        // Three columns and three rows of space
+        let input = "###\n.##\n...\n...\n...\n\n...\n...\n#..\n#.#\n###\n\n...\n..#\n#.#\n###\n###";
+        let (locks, keys) = parse(input).unwrap();
+        assert_eq!(locks[0].heights, vec![0, 1, 1]);
+        assert_eq!(locks[0].space, 3);
+        assert_eq!(keys[0].heights, vec![2, 0, 1]);
+        assert_eq!(keys[1].heights, vec![2, 1, 3]);
+        assert_eq!(fitting_keys(&locks, &keys), vec![vec![0]]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let err = |s: &str| Schematic::try_from(s).unwrap_err();
+        assert_eq!(err("###"), SchematicError::TooSmall);
+        assert_eq!(err("###\n..\n..."), SchematicError::Ragged);
+        assert_eq!(err("###\n.x.\n..."), SchematicError::BadCharacter);
+        assert_eq!(err("###\n...\n###"), SchematicError::NotLockOrKey);
+        assert_eq!(err("###\n#..\n.#.\n..."), SchematicError::BadColumn(1));
+        assert_eq!(err("...\n#..\n..#\n###"), SchematicError::BadColumn(0));
+
+        assert_eq!(
+            parse("###\n...\n\n....\n####").unwrap_err(),
+            SchematicError::SizeMismatch
+        );
+    }
 }
@@ -1,6 +1,9 @@
 use clap::Parser;
 use rayon::prelude::*;
-use std::time::Instant;
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 mod neighbor;
 
@@ -38,6 +41,188 @@ mod day25;
 struct Args {
     /// Day to run
     day: Option<usize>,
+
+    /// Which way the day 06 guard turns at obstacles
+    #[arg(long, value_enum, default_value_t = day06::TurnPolicy::Right)]
+    turn_policy: day06::TurnPolicy,
+
+    /// List every position where a new day 06 obstacle makes the guard loop
+    #[arg(long)]
+    list_obstacles: bool,
+
+    /// Draw the day 06 loop caused by a new obstacle at X,Y
+    #[arg(long, num_args = 2, value_names = ["X", "Y"])]
+    loop_obstacle: Option<Vec<usize>>,
+
+    /// Print a table of how each day 13 claw machine is won
+    #[arg(long)]
+    claw_table: bool,
+
+    /// Directory to write day 14 images to: the picture from part 2, and any frames
+    #[arg(long)]
+    robot_dir: Option<PathBuf>,
+
+    /// Also write a day 14 frame for every step from START up to (but not including) END
+    #[arg(long, num_args = 2, value_names = ["START", "END"], requires = "robot_dir")]
+    robot_frames: Option<Vec<isize>>,
+
+    /// Directory to write every frame of the day 15 warehouse to, for both parts
+    #[arg(long)]
+    warehouse_dir: Option<PathBuf>,
+
+    /// Animate the day 15 part 2 warehouse in the terminal, waiting this many milliseconds per frame
+    #[arg(long, value_name = "MS")]
+    animate_warehouse: Option<u64>,
+
+    /// Cost of moving forward one tile in the day 16 maze
+    #[arg(long, default_value_t = day16::MOVE_COST)]
+    step_cost: usize,
+
+    /// Cost of turning 90 degrees in the day 16 maze
+    #[arg(long, default_value_t = day16::TURN_COST)]
+    turn_cost: usize,
+
+    /// Draw the day 16 maze with every tile on a best path marked
+    #[arg(long)]
+    best_tiles: bool,
+
+    /// Draw up to this many distinct day 16 best paths separately
+    #[arg(long, value_name = "LIMIT")]
+    best_paths: Option<usize>,
+
+    /// Largest coordinate of the day 18 memory grid
+    #[arg(long, default_value_t = day18::MAX_DIM)]
+    memory_size: usize,
+
+    /// Number of bytes fallen for day 18 part 1
+    #[arg(long, default_value_t = day18::SIM_LIMIT)]
+    bytes: usize,
+
+    /// Draw the day 18 memory grid and shortest path after the part 1 bytes have fallen
+    #[arg(long)]
+    memory_grid: bool,
+
+    /// List the day 18 shortest path length after each byte falls
+    #[arg(long)]
+    path_lengths: bool,
+
+    /// File of day 19 towel costs, one `towel: cost` per line, to find the cheapest arrangement of
+    /// each design with
+    #[arg(long, value_name = "PATH")]
+    towel_costs: Option<PathBuf>,
+
+    /// List the ways the day 19 towels can be arranged to make this design
+    #[arg(long, value_name = "DESIGN")]
+    arrangements: Option<String>,
+
+    /// Most day 19 arrangements to list
+    #[arg(long, default_value_t = day19::ARRANGEMENT_LIMIT, requires = "arrangements")]
+    arrangement_limit: usize,
+
+    /// Longest cheat allowed for day 20 part 1
+    #[arg(long, default_value_t = day20::PT1_CHEAT_TIME)]
+    pt1_cheat_time: usize,
+
+    /// Longest cheat allowed for day 20 part 2
+    #[arg(long, default_value_t = day20::PT2_CHEAT_TIME)]
+    pt2_cheat_time: usize,
+
+    /// Minimum time a day 20 cheat must save to be counted
+    #[arg(long, default_value_t = day20::THRESHOLD)]
+    cheat_threshold: u32,
+
+    /// Show how many day 20 cheats save each amount of time, from the threshold up
+    #[arg(long)]
+    cheat_histogram: bool,
+
+    /// List every day 20 cheat which saves at least the threshold
+    #[arg(long)]
+    list_cheats: bool,
+
+    /// Number of new secret numbers each day 22 buyer generates
+    #[arg(long, default_value_t = day22::CYCLES)]
+    cycles: usize,
+
+    /// Number of consecutive day 22 price changes the monkey looks for
+    #[arg(long, default_value_t = day22::WINDOW_LEN)]
+    window_len: u32,
+
+    /// List the price each day 22 buyer sells at for the best sequence
+    #[arg(long)]
+    buyer_prices: bool,
+
+    /// Count day 23 triangles containing a computer starting with this letter
+    #[arg(long, default_value_t = day23::TRIANGLE_PREFIX)]
+    triangle_prefix: char,
+
+    /// List every maximal day 23 clique, largest first
+    #[arg(long)]
+    list_cliques: bool,
+
+    /// File to write the day 23 network to as a DOT graph, with the largest clique highlighted
+    #[arg(long, value_name = "PATH")]
+    dot: Option<PathBuf>,
+
+    /// Directory to write the day 24 circuit to, as DOT (with suspect wires highlighted) and Verilog
+    #[arg(long)]
+    circuit_dir: Option<PathBuf>,
+
+    /// List the day 25 keys that fit each lock, by pin heights
+    #[arg(long)]
+    list_fits: bool,
+}
+
+impl Args {
+    /// Run a day (indexed from 0), passing through any parameters it takes from the command line.
+    fn run_day(&self, i: usize) -> String {
+        match i {
+            5 => day06::run_outer_with(
+                self.turn_policy,
+                self.list_obstacles,
+                self.loop_obstacle.as_ref().map(|p| (p[0], p[1])),
+            ),
+            12 => day13::run_outer_with(self.claw_table),
+            13 => day14::run_outer_with(
+                self.robot_dir.as_deref(),
+                self.robot_frames.as_ref().map(|f| f[0]..f[1]),
+            ),
+            14 => day15::run_outer_with(
+                self.warehouse_dir.as_deref(),
+                self.animate_warehouse.map(Duration::from_millis),
+            ),
+            15 => day16::run_outer_with(
+                self.step_cost,
+                self.turn_cost,
+                self.best_tiles,
+                self.best_paths,
+            ),
+            17 => day18::run_outer_with(
+                self.memory_size,
+                self.bytes,
+                self.memory_grid,
+                self.path_lengths,
+            ),
+            18 => day19::run_outer_with(
+                self.towel_costs.as_deref(),
+                self.arrangements.as_deref(),
+                self.arrangement_limit,
+            ),
+            19 => day20::run_outer_with(
+                self.pt1_cheat_time,
+                self.pt2_cheat_time,
+                self.cheat_threshold,
+                self.cheat_histogram,
+                self.list_cheats,
+            ),
+            21 => day22::run_outer_with(self.cycles, self.window_len, self.buyer_prices),
+            22 => {
+                day23::run_outer_with(self.triangle_prefix, self.list_cliques, self.dot.as_deref())
+            }
+            23 => day24::run_outer_with(self.circuit_dir.as_deref()),
+            24 => day25::run_outer_with(self.list_fits),
+            _ => DAYS[i](),
+        }
+    }
 }
 
 const DAYS: [fn() -> String; 25] = [
@@ -75,24 +260,17 @@ fn main() {
 
     if let Some(day) = args.day {
         // Just run the specified day
-        if 0 == day || day >= DAYS.len() {
+        if 0 == day || day > DAYS.len() {
             eprintln!("Day {day} does not exist!");
             return;
         }
 
-        let runner = DAYS[day - 1];
-        let output = runner();
+        let output = args.run_day(day - 1);
         println!("{}", output);
     } else {
         // Run all of the days
         let start = Instant::now();
-        let results: Vec<String> = (0..25)
-            .into_par_iter()
-            .map(|i| {
-                let runner = DAYS[i];
-                runner()
-            })
-            .collect();
+        let results: Vec<String> = (0..25).into_par_iter().map(|i| args.run_day(i)).collect();
         let elapsed = Instant::now() - start;
 
         for (i, output) in results.iter().enumerate() {
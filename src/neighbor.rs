@@ -5,13 +5,18 @@ pub struct NeighborIter {
 
 impl NeighborIter {
     pub fn new(pos: (usize, usize), max_dim: usize) -> Self {
+        Self::with_bounds(pos, (max_dim, max_dim))
+    }
+
+    /// Neighbors within a rectangle from (0, 0) to `max`, inclusive.
+    pub fn with_bounds(pos: (usize, usize), max: (usize, usize)) -> Self {
         let mut vals = [None; 4];
 
         if pos.0 > 0 {
             vals[0] = Some((pos.0 - 1, pos.1));
         }
 
-        if pos.0 < max_dim {
+        if pos.0 < max.0 {
             vals[1] = Some((pos.0 + 1, pos.1));
         }
 
@@ -19,7 +24,7 @@ impl NeighborIter {
             vals[2] = Some((pos.0, pos.1 - 1));
         }
 
-        if pos.1 < max_dim {
+        if pos.1 < max.1 {
             vals[3] = Some((pos.0, pos.1 + 1));
         }
 
@@ -67,5 +72,9 @@ mod tests {
         let ni = NeighborIter::new((0, 0), 2);
         let vals: Vec<(usize, usize)> = ni.collect();
         assert_equal_unordered(vals, vec![(0, 1), (1, 0)]);
+
+        let ni = NeighborIter::with_bounds((2, 0), (2, 1));
+        let vals: Vec<(usize, usize)> = ni.collect();
+        assert_equal_unordered(vals, vec![(1, 0), (2, 1)]);
     }
 }